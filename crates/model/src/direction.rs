@@ -1,3 +1,5 @@
+use rng::Rng;
+
 #[derive(Clone, Copy, PartialEq)]
 pub(crate) enum Direction {
     Up,
@@ -7,27 +9,42 @@ pub(crate) enum Direction {
 }
 
 impl Direction {
-    pub(crate) fn maybe_turn(self, turn_chance: f32) -> Direction {
-        if !rng::gen_bool(turn_chance) {
+    pub(crate) fn maybe_turn(self, turn_chance: f32, rng: &mut Rng) -> Direction {
+        if !rng.gen_bool(turn_chance) {
             return self;
         }
 
-        if rng::gen_bool(0.5) {
-            // turn left
-            match self {
-                Direction::Up => Direction::Left,
-                Direction::Down => Direction::Right,
-                Direction::Left => Direction::Up,
-                Direction::Right => Direction::Down,
-            }
+        if rng.gen_bool(0.5) {
+            self.turn_left()
         } else {
-            // turn right
-            match self {
-                Direction::Up => Direction::Right,
-                Direction::Down => Direction::Left,
-                Direction::Left => Direction::Down,
-                Direction::Right => Direction::Up,
-            }
+            self.turn_right()
+        }
+    }
+
+    pub(crate) fn turn_left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Up,
+            Direction::Right => Direction::Down,
+        }
+    }
+
+    pub(crate) fn turn_right(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    pub(crate) fn reverse(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
         }
     }
 }
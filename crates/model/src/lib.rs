@@ -1,3 +1,4 @@
 mod direction;
 pub mod pipe;
 pub mod position;
+pub mod rngs;
@@ -1,12 +1,39 @@
 mod color;
+mod connections;
 mod kind;
 
-pub use color::{ColorMode, Palette};
+pub use color::{Background, ColorMode, Palette};
+pub use connections::{Connections, JunctionStyle};
 pub use kind::{Kind, KindSet};
 
 use self::color::Color;
+use self::connections::Arms;
 use crate::direction::Direction;
 use crate::position::{InScreenBounds, Position};
+use crate::rngs::Rngs;
+use rng::Rng;
+
+/// How hard pipes try not to run into what is already on screen.
+#[derive(Clone, Copy)]
+pub struct Avoidance<'a> {
+    /// The chance of steering away from an occupied cell when there is a free one to go to,
+    /// from 0, never, to 1, always.
+    pub strength: f32,
+    /// Whether something has been drawn at the given position.
+    pub is_occupied: &'a dyn Fn(u16, u16) -> bool,
+}
+
+/// What happens to a pipe when it goes off the edge of the screen.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeMode {
+    /// The pipe is replaced with a new one starting from a random edge.
+    Respawn,
+    /// The pipe comes back in from the opposite edge.
+    Wrap,
+    /// The pipe turns to run along the edge instead of leaving.
+    Bounce,
+}
 
 pub struct Pipe {
     current_direction: Direction,
@@ -14,12 +41,20 @@ pub struct Pipe {
     pub position: Position,
     pub color: Option<Color>,
     kind: Kind,
+    is_branch: bool,
 }
 
 impl Pipe {
-    pub fn new(size: (u16, u16), color_mode: ColorMode, palette: Palette, kind: Kind) -> Self {
-        let color = color::gen_random_color(color_mode, palette);
-        let (direction, position) = gen_random_direction_and_position(size);
+    pub fn new(
+        size: (u16, u16),
+        color_mode: ColorMode,
+        palette: Option<Palette>,
+        background: Option<Background>,
+        kind: Kind,
+        rngs: &mut Rngs,
+    ) -> Self {
+        let color = color::gen_random_color(color_mode, palette, background, &mut rngs.color);
+        let (direction, position) = gen_random_direction_and_position(size, &mut rngs.movement);
 
         Self {
             current_direction: direction,
@@ -27,11 +62,12 @@ impl Pipe {
             position,
             color,
             kind,
+            is_branch: false,
         }
     }
 
-    pub fn dup(&self, size: (u16, u16)) -> Self {
-        let (direction, position) = gen_random_direction_and_position(size);
+    pub fn dup(&self, size: (u16, u16), rng: &mut Rng) -> Self {
+        let (direction, position) = gen_random_direction_and_position(size, rng);
 
         Self {
             current_direction: direction,
@@ -39,10 +75,65 @@ impl Pipe {
             position,
             color: self.color,
             kind: self.kind,
+            is_branch: false,
         }
     }
 
-    pub fn tick(&mut self, size: (u16, u16), turn_chance: f32, hue_shift: u8) -> InScreenBounds {
+    /// Starts a pipe forking off to one side from the cell this one was last drawn in,
+    /// keeping its style, or returns `None` if there is nowhere for it to go.
+    ///
+    /// The branch is drawn in that cell first,
+    /// so its arms join with this pipe’s into a T-junction.
+    pub fn branch(&self, size: (u16, u16), edge_mode: EdgeMode, rng: &mut Rng) -> Option<Self> {
+        let came_from = self.previous_direction.reverse();
+        let sides: Vec<_> = [
+            self.current_direction.turn_left(),
+            self.current_direction.turn_right(),
+        ]
+        .into_iter()
+        .filter(|&d| d != came_from && self.next_position(d, size, edge_mode).is_some())
+        .collect();
+
+        let direction = match sides.len() {
+            0 => return None,
+            n => sides[rng.gen_range(0..n as u32) as usize],
+        };
+
+        Some(Self {
+            current_direction: direction,
+            previous_direction: self.previous_direction,
+            position: self.position,
+            color: self.color,
+            kind: self.kind,
+            is_branch: true,
+        })
+    }
+
+    /// Whether the pipe forked off another rather than starting at an edge.
+    pub fn is_branch(&self) -> bool {
+        self.is_branch
+    }
+
+    /// Gives the pipe a new random color.
+    pub fn recolor(
+        &mut self,
+        color_mode: ColorMode,
+        palette: Option<Palette>,
+        background: Option<Background>,
+        rng: &mut Rng,
+    ) {
+        self.color = color::gen_random_color(color_mode, palette, background, rng);
+    }
+
+    pub fn tick(
+        &mut self,
+        size: (u16, u16),
+        turn_chance: f32,
+        hue_shift: u8,
+        edge_mode: EdgeMode,
+        avoidance: Avoidance<'_>,
+        rng: &mut Rng,
+    ) -> InScreenBounds {
         let InScreenBounds(in_screen_bounds) = self.position.move_in(self.current_direction, size);
 
         if let Some(color) = &mut self.color {
@@ -50,15 +141,99 @@ impl Pipe {
         }
 
         if !in_screen_bounds {
-            return InScreenBounds(false);
+            match edge_mode {
+                // Bouncing pipes only get here on screens too narrow to turn in.
+                EdgeMode::Respawn | EdgeMode::Bounce => return InScreenBounds(false),
+                EdgeMode::Wrap => self.position.wrap(self.current_direction, size),
+            }
         }
 
         self.previous_direction = self.current_direction;
-        self.current_direction = self.current_direction.maybe_turn(turn_chance);
+        let mut direction = self.current_direction.maybe_turn(turn_chance, rng);
+        if let EdgeMode::Bounce = edge_mode {
+            direction = self.bounce(direction, size, rng);
+        }
+        self.current_direction = self.steer(direction, size, edge_mode, avoidance, rng);
 
         InScreenBounds(true)
     }
 
+    /// Where the pipe would be after one step in `direction`,
+    /// or `None` if that takes it off the screen.
+    fn next_position(
+        &self,
+        direction: Direction,
+        size: (u16, u16),
+        edge_mode: EdgeMode,
+    ) -> Option<Position> {
+        match (self.position.neighbor(direction, size), edge_mode) {
+            (None, EdgeMode::Wrap) => {
+                let mut position = self.position;
+                position.wrap(direction, size);
+                Some(position)
+            }
+            (next, _) => next,
+        }
+    }
+
+    /// Turns the pipe if `direction` leads off the screen.
+    fn bounce(&self, direction: Direction, size: (u16, u16), rng: &mut Rng) -> Direction {
+        if self.position.neighbor(direction, size).is_some() {
+            return direction;
+        }
+
+        let straight = self.previous_direction;
+        let onscreen: Vec<_> = [straight, straight.turn_left(), straight.turn_right()]
+            .into_iter()
+            .filter(|&d| d != direction && self.position.neighbor(d, size).is_some())
+            .collect();
+
+        match onscreen.len() {
+            0 => direction,
+            n => onscreen[rng.gen_range(0..n as u32) as usize],
+        }
+    }
+
+    /// Picks another way to go if `direction` leads into an occupied cell,
+    /// with a chance given by the avoidance’s strength.
+    fn steer(
+        &self,
+        direction: Direction,
+        size: (u16, u16),
+        edge_mode: EdgeMode,
+        avoidance: Avoidance<'_>,
+        rng: &mut Rng,
+    ) -> Direction {
+        if avoidance.strength <= 0.0 {
+            return direction;
+        }
+
+        let is_free = |direction| match self.next_position(direction, size, edge_mode) {
+            Some(position) => !(avoidance.is_occupied)(position.x, position.y),
+            None => !matches!(edge_mode, EdgeMode::Bounce),
+        };
+
+        if is_free(direction) || !rng.gen_bool(avoidance.strength) {
+            return direction;
+        }
+
+        let straight = self.previous_direction;
+        let free: Vec<_> = [straight, straight.turn_left(), straight.turn_right()]
+            .into_iter()
+            .filter(|&d| d != direction && is_free(d))
+            .collect();
+
+        match free.len() {
+            0 => direction,
+            n => free[rng.gen_range(0..n as u32) as usize],
+        }
+    }
+
+    /// The sides of its cell the pipe leads out of.
+    fn arms(&self) -> Arms {
+        Arms::new(self.previous_direction, self.current_direction)
+    }
+
     pub fn to_char(&self) -> char {
         match (self.previous_direction, self.current_direction) {
             (Direction::Up, Direction::Left) | (Direction::Right, Direction::Down) => {
@@ -82,8 +257,11 @@ impl Pipe {
     }
 }
 
-fn gen_random_direction_and_position((columns, rows): (u16, u16)) -> (Direction, Position) {
-    let direction = match rng::gen_range(0..4) {
+fn gen_random_direction_and_position(
+    (columns, rows): (u16, u16),
+    rng: &mut Rng,
+) -> (Direction, Position) {
+    let direction = match rng.gen_range(0..4) {
         0 => Direction::Up,
         1 => Direction::Down,
         2 => Direction::Left,
@@ -93,20 +271,20 @@ fn gen_random_direction_and_position((columns, rows): (u16, u16)) -> (Direction,
 
     let position = match direction {
         Direction::Up => Position {
-            x: rng::gen_range_16(0..columns),
+            x: rng.gen_range_16(0..columns),
             y: rows - 1,
         },
         Direction::Down => Position {
-            x: rng::gen_range_16(0..columns),
+            x: rng.gen_range_16(0..columns),
             y: 0,
         },
         Direction::Left => Position {
             x: columns - 1,
-            y: rng::gen_range_16(0..rows),
+            y: rng.gen_range_16(0..rows),
         },
         Direction::Right => Position {
             x: 0,
-            y: rng::gen_range_16(0..rows),
+            y: rng.gen_range_16(0..rows),
         },
     };
 
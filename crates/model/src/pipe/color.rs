@@ -1,97 +1,263 @@
+use rng::Rng;
 use std::ops::Range;
+use std::sync::OnceLock;
+use tincture::{Oklab, Oklch};
 
 #[derive(Clone, Copy)]
 pub struct Color {
     pub terminal: terminal::Color,
-    pub(crate) oklch: Option<tincture::Oklch>,
+    pub(crate) oklch: Option<Oklch>,
+    mode: ColorMode,
 }
 
 impl Color {
     pub(crate) fn update(&mut self, hue_shift: f32) {
         if let Some(oklch) = &mut self.oklch {
             oklch.h += hue_shift.to_radians();
-            let oklab = tincture::oklch_to_oklab(*oklch);
-            let lrgb = tincture::oklab_to_linear_srgb(oklab);
-            let srgb = tincture::linear_srgb_to_srgb(lrgb);
-            self.terminal = terminal::Color::Rgb {
-                r: (srgb.r * 255.0) as u8,
-                g: (srgb.g * 255.0) as u8,
-                b: (srgb.b * 255.0) as u8,
-            };
+            self.terminal = to_terminal_color(*oklch, self.mode);
         }
     }
 }
 
-pub(super) fn gen_random_color(color_mode: ColorMode, palette: Palette) -> Option<Color> {
-    match color_mode {
-        ColorMode::Ansi => Some(gen_random_ansi_color()),
-        ColorMode::Rgb => Some(gen_random_rgb_color(palette)),
-        ColorMode::None => None,
+pub(super) fn gen_random_color(
+    color_mode: ColorMode,
+    palette: Option<Palette>,
+    background: Option<Background>,
+    rng: &mut Rng,
+) -> Option<Color> {
+    match (color_mode, palette) {
+        (ColorMode::Ansi, None) => Some(gen_random_ansi_color(rng)),
+        (ColorMode::Ansi | ColorMode::Ansi256 | ColorMode::Rgb | ColorMode::Auto, _) => Some(
+            gen_random_rgb_color(palette.unwrap_or_default(), background, color_mode, rng),
+        ),
+        (ColorMode::None, _) => None,
     }
 }
 
-fn gen_random_ansi_color() -> Color {
-    let num = rng::gen_range(0..12);
+const ANSI_COLORS: [terminal::Color; 12] = [
+    terminal::Color::Red,
+    terminal::Color::DarkRed,
+    terminal::Color::Green,
+    terminal::Color::DarkGreen,
+    terminal::Color::Yellow,
+    terminal::Color::DarkYellow,
+    terminal::Color::Blue,
+    terminal::Color::DarkBlue,
+    terminal::Color::Magenta,
+    terminal::Color::DarkMagenta,
+    terminal::Color::Cyan,
+    terminal::Color::DarkCyan,
+];
+
+fn gen_random_ansi_color(rng: &mut Rng) -> Color {
+    let num = rng.gen_range(0..ANSI_COLORS.len() as u32);
 
     Color {
-        terminal: match num {
-            0 => terminal::Color::Red,
-            1 => terminal::Color::DarkRed,
-            2 => terminal::Color::Green,
-            3 => terminal::Color::DarkGreen,
-            4 => terminal::Color::Yellow,
-            5 => terminal::Color::DarkYellow,
-            6 => terminal::Color::Blue,
-            7 => terminal::Color::DarkBlue,
-            8 => terminal::Color::Magenta,
-            9 => terminal::Color::DarkMagenta,
-            10 => terminal::Color::Cyan,
-            11 => terminal::Color::DarkCyan,
-            _ => unreachable!(),
-        },
+        terminal: ANSI_COLORS[num as usize],
         oklch: None,
+        mode: ColorMode::Ansi,
     }
 }
 
-fn gen_random_rgb_color(palette: Palette) -> Color {
-    let hue = rng::gen_range_float(palette.get_hue_range());
-    let lightness = rng::gen_range_float(palette.get_lightness_range());
+/// Picks a color from the palette in OKLCH,
+/// approximating it as closely as the color mode allows.
+fn gen_random_rgb_color(
+    palette: Palette,
+    background: Option<Background>,
+    color_mode: ColorMode,
+    rng: &mut Rng,
+) -> Color {
+    let hue = rng.gen_range_float(palette.get_hue_range());
+    let lightness_range = match background {
+        Some(background) => background.contrasting(palette.get_lightness_range()),
+        None => palette.get_lightness_range(),
+    };
+    let lightness = rng.gen_range_float(lightness_range);
 
-    let oklch = tincture::Oklch {
+    let mut oklch = Oklch {
         l: lightness,
         c: palette.get_chroma(),
         h: hue.to_radians(),
     };
-    let oklab = tincture::oklch_to_oklab(oklch);
-    let lrgb = tincture::oklab_to_linear_srgb(oklab);
-    let srgb = tincture::linear_srgb_to_srgb(lrgb);
-    debug_assert!(
-        (0.0..=1.0).contains(&srgb.r)
-            && (0.0..=1.0).contains(&srgb.g)
-            && (0.0..=1.0).contains(&srgb.b)
-    );
+
+    // The palettes are all within sRGB as they are,
+    // but lightening or darkening them for contrast can push some hues out.
+    while !is_in_gamut(oklch) && oklch.c > 0.0 {
+        oklch.c = (oklch.c - 0.005).max(0.0);
+    }
 
     Color {
-        terminal: terminal::Color::Rgb {
-            r: (srgb.r * 255.0) as u8,
-            g: (srgb.g * 255.0) as u8,
-            b: (srgb.b * 255.0) as u8,
-        },
+        terminal: to_terminal_color(oklch, color_mode),
         oklch: Some(oklch),
+        mode: color_mode,
     }
 }
 
+fn to_terminal_color(oklch: Oklch, color_mode: ColorMode) -> terminal::Color {
+    static ANSI: OnceLock<Vec<(terminal::Color, Oklab)>> = OnceLock::new();
+    static ANSI_256: OnceLock<Vec<(terminal::Color, Oklab)>> = OnceLock::new();
+
+    let oklab = tincture::oklch_to_oklab(oklch);
+
+    match color_mode {
+        ColorMode::Ansi => nearest(oklab, ANSI.get_or_init(|| candidates(ANSI_COLORS)), false),
+        // The first 16 entries are often customized by the user,
+        // so only the color cube and the greyscale ramp can be relied on.
+        ColorMode::Ansi256 => nearest(
+            oklab,
+            ANSI_256.get_or_init(|| candidates((16..=255).map(terminal::Color::AnsiValue))),
+            true,
+        ),
+        ColorMode::Rgb | ColorMode::Auto | ColorMode::None => {
+            let srgb = oklch_to_srgb(oklch);
+
+            terminal::Color::Rgb {
+                r: (srgb.r * 255.0) as u8,
+                g: (srgb.g * 255.0) as u8,
+                b: (srgb.b * 255.0) as u8,
+            }
+        }
+    }
+}
+
+fn is_in_gamut(oklch: Oklch) -> bool {
+    let srgb = oklch_to_srgb(oklch);
+    [srgb.r, srgb.g, srgb.b]
+        .iter()
+        .all(|c| (0.0..=1.0).contains(c))
+}
+
+fn oklch_to_srgb(oklch: Oklch) -> tincture::Srgb {
+    let oklab = tincture::oklch_to_oklab(oklch);
+    let lrgb = tincture::oklab_to_linear_srgb(oklab);
+    tincture::linear_srgb_to_srgb(lrgb)
+}
+
+fn candidates(colors: impl IntoIterator<Item = terminal::Color>) -> Vec<(terminal::Color, Oklab)> {
+    colors
+        .into_iter()
+        .map(|color| {
+            let (r, g, b) = color.to_rgb();
+            let srgb = tincture::Srgb {
+                r: f32::from(r) / 255.0,
+                g: f32::from(g) / 255.0,
+                b: f32::from(b) / 255.0,
+            };
+            let lrgb = tincture::srgb_to_linear_srgb(srgb);
+
+            (color, tincture::linear_srgb_to_oklab(lrgb))
+        })
+        .collect()
+}
+
+/// Finds the candidate that looks most like `oklab`.
+///
+/// The named ANSI colors are all far more saturated than any palette,
+/// so rather than comparing chroma, which would favor whichever is least saturated,
+/// hue and lightness are compared separately.
+fn nearest(
+    oklab: Oklab,
+    candidates: &[(terminal::Color, Oklab)],
+    compare_chroma: bool,
+) -> terminal::Color {
+    let hue = |c: &Oklab| {
+        let chroma = c.a.hypot(c.b).max(f32::EPSILON);
+        (c.a / chroma, c.b / chroma)
+    };
+    let distance = |other: &Oklab| {
+        let dl = oklab.l - other.l;
+        if compare_chroma {
+            dl.powi(2) + (oklab.a - other.a).powi(2) + (oklab.b - other.b).powi(2)
+        } else {
+            let ((a1, b1), (a2, b2)) = (hue(&oklab), hue(other));
+            dl.powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)
+        }
+    };
+
+    candidates
+        .iter()
+        .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
 #[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ColorMode {
+    Auto,
     Ansi,
+    Ansi256,
     Rgb,
     None,
 }
 
-#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+impl ColorMode {
+    /// Replaces `Auto` with the best mode the terminal supports.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => match terminal::ColorSupport::detect() {
+                terminal::ColorSupport::None => Self::None,
+                terminal::ColorSupport::Ansi => Self::Ansi,
+                terminal::ColorSupport::Ansi256 => Self::Ansi256,
+                terminal::ColorSupport::TrueColor => Self::Rgb,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// The terminal’s background color, which pipes are kept readable against.
+#[derive(Clone, Copy)]
+pub struct Background {
+    lightness: f32,
+}
+
+/// How far apart in OKLCH lightness pipes are kept from the background.
+const MIN_CONTRAST: f32 = 0.35;
+
+impl Background {
+    pub fn new(color: terminal::Color) -> Self {
+        let (r, g, b) = color.to_rgb();
+        let srgb = tincture::Srgb {
+            r: f32::from(r) / 255.0,
+            g: f32::from(g) / 255.0,
+            b: f32::from(b) / 255.0,
+        };
+        let oklab = tincture::linear_srgb_to_oklab(tincture::srgb_to_linear_srgb(srgb));
+
+        Self { lightness: oklab.l }
+    }
+
+    pub fn is_light(self) -> bool {
+        self.lightness > 0.5
+    }
+
+    /// The palette designed for this kind of background.
+    pub fn palette(self) -> Palette {
+        if self.is_light() {
+            Palette::Darker
+        } else {
+            Palette::Default
+        }
+    }
+
+    /// Narrows a lightness range so every color in it
+    /// is at least [`MIN_CONTRAST`] lighter or darker than the background.
+    fn contrasting(self, range: Range<f32>) -> Range<f32> {
+        if self.is_light() {
+            let max = self.lightness - MIN_CONTRAST;
+            range.start.min(max)..range.end.min(max)
+        } else {
+            let min = self.lightness + MIN_CONTRAST;
+            range.start.max(min)..range.end.max(min)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Palette {
+    #[default]
     Default,
     Darker,
     Pastel,
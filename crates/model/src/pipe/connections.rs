@@ -0,0 +1,124 @@
+use super::Pipe;
+use crate::direction::Direction;
+use std::ops::BitOr;
+
+/// The sides of a cell that the pipes drawn in it lead out of.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Arms(u8);
+
+impl Arms {
+    const VERTICAL: Self = Self(Self::bit(Direction::Up) | Self::bit(Direction::Down));
+    const HORIZONTAL: Self = Self(Self::bit(Direction::Left) | Self::bit(Direction::Right));
+
+    /// The arms of a pipe that came in moving `from` and leaves moving `to`.
+    pub(crate) fn new(from: Direction, to: Direction) -> Self {
+        Self(Self::bit(from.reverse()) | Self::bit(to))
+    }
+
+    pub(crate) fn has(self, direction: Direction) -> bool {
+        self.0 & Self::bit(direction) != 0
+    }
+
+    fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    const fn bit(direction: Direction) -> u8 {
+        match direction {
+            Direction::Up => 1,
+            Direction::Down => 2,
+            Direction::Left => 4,
+            Direction::Right => 8,
+        }
+    }
+}
+
+impl BitOr for Arms {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// How to draw a pipe over a cell another pipe has already been drawn in.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JunctionStyle {
+    /// Replace whatever was there, as if the older pipe wasn’t.
+    Overwrite,
+    /// Join the pipes with a junction or crossing.
+    Join,
+    /// Like `Join`, except a pipe going straight across another passes over it,
+    /// drawn with a heavier or doubled line.
+    Bridge,
+}
+
+/// Remembers which sides every cell on screen connects to,
+/// so pipes meeting in a cell can be joined.
+pub struct Connections {
+    arms: Vec<Arms>,
+    width: u16,
+    height: u16,
+}
+
+impl Connections {
+    pub fn new((width, height): (u16, u16)) -> Self {
+        Self {
+            arms: vec![Arms::default(); usize::from(width) * usize::from(height)],
+            width,
+            height,
+        }
+    }
+
+    /// Changes the size of the screen, keeping the connections of cells that still fit.
+    pub fn resize(&mut self, size: (u16, u16)) {
+        let mut resized = Self::new(size);
+        for y in 0..self.height.min(resized.height) {
+            for x in 0..self.width.min(resized.width) {
+                let index = resized.index(x, y);
+                resized.arms[index] = self.arms[self.index(x, y)];
+            }
+        }
+
+        *self = resized;
+    }
+
+    /// Records `pipe` being drawn where it is,
+    /// returning the character to draw there.
+    pub fn join(&mut self, pipe: &Pipe, style: JunctionStyle) -> char {
+        let index = self.index(pipe.position.x, pipe.position.y);
+        let existing = self.arms[index];
+        let own = pipe.arms();
+
+        let passes_over = (own == Arms::HORIZONTAL && existing.contains(Arms::VERTICAL))
+            || (own == Arms::VERTICAL && existing.contains(Arms::HORIZONTAL));
+
+        if let (JunctionStyle::Bridge, true) = (style, passes_over) {
+            if let Some(c) = pipe.kind.bridge(own == Arms::VERTICAL) {
+                self.arms[index] = existing | own;
+                return c;
+            }
+        }
+
+        let arms = match style {
+            JunctionStyle::Overwrite => own,
+            JunctionStyle::Join | JunctionStyle::Bridge => existing | own,
+        };
+
+        match pipe.kind.junction(arms) {
+            Some(c) => {
+                self.arms[index] = arms;
+                c
+            }
+            None => {
+                self.arms[index] = own;
+                pipe.to_char()
+            }
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        usize::from(y) * usize::from(self.width) + usize::from(x)
+    }
+}
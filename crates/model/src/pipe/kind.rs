@@ -1,3 +1,6 @@
+use super::connections::Arms;
+use crate::direction::Direction;
+use rng::Rng;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 
@@ -48,6 +51,40 @@ impl Kind {
         self.chars()[7]
     }
 
+    /// The character for a junction of three or four arms,
+    /// if this kind has one.
+    pub(crate) fn junction(self, arms: Arms) -> Option<char> {
+        let [no_up, no_down, no_left, no_right, cross] = self.junctions()?;
+
+        let arms = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .map(|direction| arms.has(direction));
+
+        match arms {
+            [false, true, true, true] => Some(no_up),
+            [true, false, true, true] => Some(no_down),
+            [true, true, false, true] => Some(no_left),
+            [true, true, true, false] => Some(no_right),
+            [true, true, true, true] => Some(cross),
+            _ => None,
+        }
+    }
+
+    /// The character for a pipe going straight across another,
+    /// if this kind has one.
+    pub(crate) fn bridge(self, vertical: bool) -> Option<char> {
+        let [vertical_over, horizontal_over] = self.bridges()?;
+        Some(if vertical {
+            vertical_over
+        } else {
+            horizontal_over
+        })
+    }
+
     fn chars(self) -> [char; 8] {
         match self {
             Self::Heavy => Self::HEAVY,
@@ -62,6 +99,28 @@ impl Kind {
         }
     }
 
+    /// The junctions missing the up, down, left and right arm in turn,
+    /// followed by the crossing of all four.
+    fn junctions(self) -> Option<[char; 5]> {
+        match self {
+            Self::Heavy => Some(Self::HEAVY_JUNCTIONS),
+            Self::Light | Self::Curved => Some(Self::LIGHT_JUNCTIONS),
+            Self::Knobby => Some(Self::KNOBBY_JUNCTIONS),
+            Self::Outline => Some(Self::OUTLINE_JUNCTIONS),
+            Self::Emoji | Self::Dots | Self::Blocks | Self::Sus => None,
+        }
+    }
+
+    /// The crossings where a vertical pipe passes over a horizontal one,
+    /// followed by the other way around.
+    fn bridges(self) -> Option<[char; 2]> {
+        match self {
+            Self::Heavy => Some(Self::HEAVY_BRIDGES),
+            Self::Light | Self::Curved | Self::Knobby | Self::Outline => Some(Self::DOUBLE_BRIDGES),
+            Self::Emoji | Self::Dots | Self::Blocks | Self::Sus => None,
+        }
+    }
+
     fn width(self) -> KindWidth {
         match self {
             Self::Dots | Self::Sus => KindWidth::Custom(NonZeroUsize::new(2).unwrap()),
@@ -78,6 +137,14 @@ impl Kind {
     const DOTS: [char; 8] = ['•', '•', '•', '•', '•', '•', '•', '•'];
     const BLOCKS: [char; 8] = ['█', '█', '▀', '▀', '█', '█', '▀', '▀'];
     const SUS: [char; 8] = ['ඞ', 'ඞ', 'ඞ', 'ඞ', 'ඞ', 'ඞ', 'ඞ', 'ඞ'];
+
+    const HEAVY_JUNCTIONS: [char; 5] = ['┳', '┻', '┣', '┫', '╋'];
+    const LIGHT_JUNCTIONS: [char; 5] = ['┬', '┴', '├', '┤', '┼'];
+    const KNOBBY_JUNCTIONS: [char; 5] = ['┰', '┸', '┠', '┨', '╂'];
+    const OUTLINE_JUNCTIONS: [char; 5] = ['╦', '╩', '╠', '╣', '╬'];
+
+    const HEAVY_BRIDGES: [char; 2] = ['╂', '┿'];
+    const DOUBLE_BRIDGES: [char; 2] = ['╫', '╪'];
 }
 
 #[derive(Clone, Copy)]
@@ -125,13 +192,18 @@ impl KindSet {
         Self(vec![kind])
     }
 
-    pub fn choose_random(&self) -> Kind {
-        let idx = rng::gen_range(0..self.0.len() as u32);
+    pub fn choose_random(&self, rng: &mut Rng) -> Kind {
+        let idx = rng.gen_range(0..self.0.len() as u32);
         self.0[idx as usize]
     }
 
     pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
-        self.0.iter().flat_map(|kind| kind.chars())
+        self.0.iter().flat_map(|kind| {
+            kind.chars()
+                .into_iter()
+                .chain(kind.junctions().into_iter().flatten())
+                .chain(kind.bridges().into_iter().flatten())
+        })
     }
 
     pub fn custom_widths(&self) -> impl Iterator<Item = NonZeroUsize> + '_ {
@@ -1,5 +1,6 @@
 use crate::direction::Direction;
 
+#[derive(Clone, Copy)]
 pub struct Position {
     pub x: u16,
     pub y: u16,
@@ -27,6 +28,22 @@ impl Position {
         InScreenBounds(self.in_screen_bounds(size))
     }
 
+    /// Moves onto the opposite edge after [`Position::move_in`] left the screen in `dir`.
+    pub(crate) fn wrap(&mut self, dir: Direction, (columns, rows): (u16, u16)) {
+        match dir {
+            Direction::Up => self.y = rows - 1,
+            Direction::Down => self.y = 0,
+            Direction::Left => self.x = columns - 1,
+            Direction::Right => self.x = 0,
+        }
+    }
+
+    /// The position one step in `dir`, if that is still on screen.
+    pub(crate) fn neighbor(mut self, dir: Direction, size: (u16, u16)) -> Option<Position> {
+        let InScreenBounds(in_screen_bounds) = self.move_in(dir, size);
+        in_screen_bounds.then_some(self)
+    }
+
     fn in_screen_bounds(&self, (columns, rows): (u16, u16)) -> bool {
         self.x < columns && self.y < rows
     }
@@ -0,0 +1,21 @@
+use rng::Rng;
+
+/// Independent random streams for each aspect of the simulation.
+///
+/// Keeping these apart means that, for example,
+/// changing the palette doesn’t change the paths pipes take.
+pub struct Rngs {
+    pub movement: Rng,
+    pub color: Rng,
+    pub kind: Rng,
+}
+
+impl Rngs {
+    pub fn new(movement_seed: u64, color_seed: u64, kind_seed: u64) -> Self {
+        Self {
+            movement: Rng::with_stream(movement_seed, 1),
+            color: Rng::with_stream(color_seed, 2),
+            kind: Rng::with_stream(kind_seed, 3),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Measures how long it takes to simulate and draw a busy screen,
+//! writing to the null device through a line-buffered writer like stdout’s
+//! so that the cost of each write to the terminal is included.
+//!
+//! Run with `cargo bench -p pipes-rs`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pipes_rs::{App, Config};
+use std::fs::File;
+use std::io::LineWriter;
+use terminal::WriteBackend;
+
+const SIZE: (u16, u16) = (200, 60);
+const TICKS: u32 = 100;
+
+fn null_device() -> LineWriter<File> {
+    let path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    LineWriter::new(File::create(path).unwrap())
+}
+
+fn config() -> Config {
+    Config {
+        num_pipes: Some(500),
+        reset_threshold: Some(0.0),
+        seed: Some(1),
+        ..Config::default()
+    }
+}
+
+fn render(c: &mut Criterion) {
+    c.bench_function("500 pipes, 100 ticks", |b| {
+        b.iter_batched(
+            || App::with_backend(config(), WriteBackend::new(null_device(), SIZE)).unwrap(),
+            |mut app| app.run_headless(Some(TICKS), None).unwrap(),
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
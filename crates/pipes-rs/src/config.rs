@@ -1,5 +1,6 @@
+use crate::export;
 use anyhow::Context;
-use model::pipe::{ColorMode, Kind, KindSet, Palette};
+use model::pipe::{ColorMode, EdgeMode, JunctionStyle, Kind, KindSet, Palette};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
@@ -16,8 +17,29 @@ pub struct Config {
     pub kinds: Option<KindSet>,
     pub bold: Option<bool>,
     pub inherit_style: Option<bool>,
+    pub edge_mode: Option<EdgeMode>,
     pub num_pipes: Option<u32>,
+    pub max_pipes: Option<u32>,
+    pub branch_chance: Option<f32>,
+    pub inherit_branch_color: Option<bool>,
     pub turn_chance: Option<f32>,
+    pub avoidance: Option<f32>,
+    pub junctions: Option<JunctionStyle>,
+    pub screensaver: Option<bool>,
+    /// Whether to use synchronized output, or `None` to use it if the terminal supports it.
+    pub sync_output: Option<bool>,
+    pub seed: Option<u64>,
+    pub movement_seed: Option<u64>,
+    pub color_seed: Option<u64>,
+    pub kind_seed: Option<u64>,
+    pub print_seed: Option<bool>,
+    pub record: Option<PathBuf>,
+    pub render: Option<PathBuf>,
+    pub export: Option<PathBuf>,
+    pub ticks: Option<u32>,
+    pub coverage: Option<f32>,
+    pub print: Option<bool>,
+    pub size: Option<(u16, u16)>,
 }
 
 impl Config {
@@ -72,19 +94,69 @@ impl Config {
             anyhow::bail!("turn chance should be within 0 and 1")
         }
 
+        if !(0.0..=1.0).contains(&self.branch_chance()) {
+            anyhow::bail!("branch chance should be within 0 and 1")
+        }
+
+        if self.num_pipes() > self.max_pipes() {
+            anyhow::bail!("pipe count can’t exceed the maximum number of pipes");
+        }
+
+        if !(0.0..=1.0).contains(&self.avoidance()) {
+            anyhow::bail!("avoidance should be within 0 and 1")
+        }
+
         if self.delay_ms.is_some() && self.fps.is_some() {
             anyhow::bail!("both delay and FPS can’t be set simultaneously");
         }
 
+        if self.is_headless()
+            && self.record.is_none()
+            && self.render.is_none()
+            && self.export.is_none()
+            && !self.print()
+        {
+            anyhow::bail!(
+                "a number of ticks or coverage can only be set when recording, rendering, exporting or printing"
+            );
+        }
+
+        if let Some(export) = &self.export {
+            export::Format::from_path(export)?;
+        }
+
+        if (self.render.is_some() || self.print()) && !self.is_headless() {
+            anyhow::bail!("a number of ticks or coverage is needed when rendering or printing");
+        }
+
+        if let Some(coverage) = self.coverage {
+            if !(0.0..=1.0).contains(&coverage) {
+                anyhow::bail!("coverage should be within 0 and 1");
+            }
+
+            if let Some(reset_threshold) = self.reset_threshold() {
+                if coverage > reset_threshold {
+                    anyhow::bail!("coverage can’t be higher than the reset threshold");
+                }
+            }
+        }
+
+        if let Some((0, _) | (_, 0)) = self.size {
+            anyhow::bail!("size should be at least 1×1");
+        }
+
+        let (max_width, max_height) = export::MAX_GIF_SIZE;
+        if let (Some(_), Some((width, height))) = (&self.render, self.size) {
+            if width > max_width || height > max_height {
+                anyhow::bail!("size should be at most {max_width}×{max_height} when rendering");
+            }
+        }
+
         Ok(())
     }
 
     pub fn color_mode(&self) -> ColorMode {
-        self.color_mode.unwrap_or(ColorMode::Ansi)
-    }
-
-    pub fn palette(&self) -> Palette {
-        self.palette.unwrap_or(Palette::Default)
+        self.color_mode.unwrap_or(ColorMode::Auto)
     }
 
     pub fn rainbow(&self) -> u8 {
@@ -128,11 +200,97 @@ impl Config {
         self.inherit_style.unwrap_or(false)
     }
 
+    pub fn edge_mode(&self) -> EdgeMode {
+        self.edge_mode.unwrap_or(EdgeMode::Respawn)
+    }
+
     pub fn num_pipes(&self) -> u32 {
         self.num_pipes.unwrap_or(1)
     }
 
+    /// The most pipes there can be at once, counting branches.
+    ///
+    /// Unless set, this leaves room for at least as many pipes as start out on screen.
+    pub fn max_pipes(&self) -> u32 {
+        self.max_pipes.unwrap_or_else(|| self.num_pipes().max(100))
+    }
+
+    pub fn branch_chance(&self) -> f32 {
+        self.branch_chance.unwrap_or(0.0)
+    }
+
+    pub fn inherit_branch_color(&self) -> bool {
+        self.inherit_branch_color.unwrap_or(true)
+    }
+
     pub fn turn_chance(&self) -> f32 {
         self.turn_chance.unwrap_or(0.15)
     }
+
+    pub fn avoidance(&self) -> f32 {
+        self.avoidance.unwrap_or(0.0)
+    }
+
+    pub fn junctions(&self) -> JunctionStyle {
+        self.junctions.unwrap_or(JunctionStyle::Join)
+    }
+
+    pub fn screensaver(&self) -> bool {
+        self.screensaver.unwrap_or(false)
+    }
+
+    pub fn print_seed(&self) -> bool {
+        self.print_seed.unwrap_or(false)
+    }
+
+    /// Whether to run without a terminal until a number of ticks or coverage is reached.
+    pub fn is_headless(&self) -> bool {
+        self.ticks.is_some() || self.coverage.is_some()
+    }
+
+    pub fn print(&self) -> bool {
+        self.print.unwrap_or(false)
+    }
+
+    /// The size of the screen when running headlessly.
+    pub fn size(&self) -> (u16, u16) {
+        self.size.unwrap_or((80, 24))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_pipes_defaults_to_room_for_every_pipe() {
+        assert_eq!(Config::default().max_pipes(), 100);
+
+        let config = Config {
+            num_pipes: Some(200),
+            ..Config::default()
+        };
+        assert_eq!(config.max_pipes(), 200);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn more_pipes_than_the_maximum() {
+        let config = Config {
+            num_pipes: Some(20),
+            max_pipes: Some(10),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "pipe count can’t exceed the maximum number of pipes"
+        );
+
+        let config = Config {
+            num_pipes: Some(10),
+            max_pipes: Some(10),
+            ..Config::default()
+        };
+        assert!(config.validate().is_ok());
+    }
 }
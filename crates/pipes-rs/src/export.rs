@@ -0,0 +1,250 @@
+mod font;
+mod gif;
+mod html;
+mod svg;
+mod text;
+
+pub use self::gif::GifBackend;
+pub(crate) use self::gif::MAX_SIZE as MAX_GIF_SIZE;
+pub(crate) use self::text::render as text;
+
+use anyhow::Context;
+use std::fs;
+use std::path::Path;
+use terminal::{Color, Grid};
+use unicode_width::UnicodeWidthChar;
+
+const BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+const FOREGROUND: (u8, u8, u8) = (229, 229, 229);
+
+/// Formats a still frame can be exported to, chosen by file extension.
+#[derive(Clone, Copy)]
+pub(crate) enum Format {
+    Svg,
+    Html,
+}
+
+impl Format {
+    pub(crate) fn from_path(path: &Path) -> anyhow::Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => Ok(Self::Svg),
+            Some("html" | "htm") => Ok(Self::Html),
+            _ => anyhow::bail!(
+                "unknown export format for “{}” (expected a .svg or .html file)",
+                path.display()
+            ),
+        }
+    }
+}
+
+pub(crate) fn export(grid: &Grid, path: &Path) -> anyhow::Result<()> {
+    let contents = match Format::from_path(path)? {
+        Format::Svg => svg::render(grid),
+        Format::Html => html::render(grid),
+    };
+
+    fs::write(path, contents).with_context(|| format!("failed to write “{}”", path.display()))
+}
+
+/// A stretch of text on one row that shares a single style.
+struct Run {
+    x: u16,
+    text: String,
+    color: Option<Color>,
+    bold: bool,
+}
+
+impl Run {
+    /// Returns the CSS classes for the run’s style,
+    /// along with its color if that isn’t one of the named ones.
+    fn style(&self) -> (String, Option<String>) {
+        let mut classes = Vec::new();
+        let mut rgb = None;
+
+        if let Some(color) = self.color {
+            match ansi_class(color) {
+                Some(class) => classes.push(class),
+                None => rgb = Some(hex(color.to_rgb())),
+            }
+        }
+
+        if self.bold {
+            classes.push("bold");
+        }
+
+        (classes.join(" "), rgb)
+    }
+}
+
+/// Splits a row into runs of identically styled text.
+///
+/// Gaps between cells of the same style are filled with spaces
+/// rather than starting a new run,
+/// which keeps pipes drawn with wide spacing in one piece.
+fn runs(grid: &Grid, y: u16) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut end = 0;
+    let mut x = 0;
+
+    while x < grid.width() {
+        let Some(cell) = grid.get(x, y) else {
+            x += 1;
+            continue;
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.color == cell.color && run.bold == cell.bold => {
+                run.text.push_str(&" ".repeat((x - end) as usize));
+                run.text.push(cell.c);
+            }
+            _ => runs.push(Run {
+                x,
+                text: cell.c.to_string(),
+                color: cell.color,
+                bold: cell.bold,
+            }),
+        }
+
+        x += cell.c.width().unwrap_or(1).max(1) as u16;
+        end = x;
+    }
+
+    runs
+}
+
+/// Names a CSS class for each of the named ANSI colors,
+/// so they can be restyled to match a terminal’s own palette.
+fn ansi_class(color: Color) -> Option<&'static str> {
+    let class = match color {
+        Color::Red => "red",
+        Color::DarkRed => "dark-red",
+        Color::Green => "green",
+        Color::DarkGreen => "dark-green",
+        Color::Yellow => "yellow",
+        Color::DarkYellow => "dark-yellow",
+        Color::Blue => "blue",
+        Color::DarkBlue => "dark-blue",
+        Color::Magenta => "magenta",
+        Color::DarkMagenta => "dark-magenta",
+        Color::Cyan => "cyan",
+        Color::DarkCyan => "dark-cyan",
+        Color::AnsiValue(_) | Color::Rgb { .. } => return None,
+    };
+
+    Some(class)
+}
+
+const ANSI_COLORS: [Color; 12] = [
+    Color::Red,
+    Color::DarkRed,
+    Color::Green,
+    Color::DarkGreen,
+    Color::Yellow,
+    Color::DarkYellow,
+    Color::Blue,
+    Color::DarkBlue,
+    Color::Magenta,
+    Color::DarkMagenta,
+    Color::Cyan,
+    Color::DarkCyan,
+];
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    //! Golden-file tests for each export format,
+    //! compared against `tests/snapshots/export_*`.
+    //!
+    //! Run with `UPDATE_SNAPSHOTS=1` to rewrite the expected output.
+
+    use super::*;
+    use crate::test_common::assert_snapshot_file;
+    use crate::Config;
+    use model::pipe::ColorMode;
+    use std::io::Write;
+    use terminal::GridBackend;
+
+    /// A grid with characters that need escaping,
+    /// gaps inside runs of one style, and named, RGB and default colors.
+    fn escaping_grid() -> Grid {
+        let mut backend = GridBackend::new(8, 3);
+        write!(
+            backend,
+            "\x1b[31m\x1b[1;1H<\x1b[1;3H&\x1b[1;5H\"\x1b[38;2;18;52;86m\x1b[1;6H━━\
+             \x1b[0m\x1b[1m\x1b[2;2H┃\x1b[2;4H┃"
+        )
+        .unwrap();
+        backend.grid().clone()
+    }
+
+    fn seeded_grid() -> Grid {
+        let config = Config {
+            color_mode: Some(ColorMode::Ansi),
+            reset_threshold: Some(0.0),
+            num_pipes: Some(2),
+            seed: Some(1),
+            ..Config::default()
+        };
+        crate::simulate(config, (12, 5), 20).unwrap()
+    }
+
+    #[test]
+    fn runs_merge_across_gaps() {
+        let grid = escaping_grid();
+
+        let texts: Vec<_> = runs(&grid, 0).into_iter().map(|run| run.text).collect();
+        assert_eq!(texts, ["< & \"", "━━"]);
+
+        let texts: Vec<_> = runs(&grid, 1).into_iter().map(|run| run.text).collect();
+        assert_eq!(texts, ["┃ ┃"]);
+
+        assert!(runs(&grid, 2).is_empty());
+    }
+
+    #[test]
+    fn svg_escaping() {
+        assert_snapshot_file("export_escaping.svg", &svg::render(&escaping_grid()));
+    }
+
+    #[test]
+    fn html_escaping() {
+        assert_snapshot_file("export_escaping.html", &html::render(&escaping_grid()));
+    }
+
+    #[test]
+    fn text_escaping() {
+        assert_snapshot_file("export_escaping.txt", &text(&escaping_grid(), false));
+    }
+
+    #[test]
+    fn svg_seeded() {
+        assert_snapshot_file("export_seeded.svg", &svg::render(&seeded_grid()));
+    }
+
+    #[test]
+    fn html_seeded() {
+        assert_snapshot_file("export_seeded.html", &html::render(&seeded_grid()));
+    }
+
+    #[test]
+    fn text_seeded() {
+        assert_snapshot_file("export_seeded.txt", &text(&seeded_grid(), true));
+    }
+}
@@ -0,0 +1,338 @@
+//! A tiny bitmap font covering the glyphs pipes are drawn with.
+//!
+//! Box-drawing characters are built up from their four arms,
+//! so every combination of weights lines up with its neighbours;
+//! the remaining glyphs are drawn by hand.
+
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+pub(super) const CELL_WIDTH: usize = 8;
+pub(super) const CELL_HEIGHT: usize = 16;
+
+/// A rasterized glyph, one bit per pixel, spanning one or two cells.
+pub(super) struct Glyph {
+    width: usize,
+    rows: [u16; CELL_HEIGHT],
+}
+
+impl Glyph {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            rows: [0; CELL_HEIGHT],
+        }
+    }
+
+    pub(super) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(super) fn get(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & (1 << x) != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, on: bool) {
+        if on {
+            self.rows[y] |= 1 << x;
+        } else {
+            self.rows[y] &= !(1 << x);
+        }
+    }
+
+    fn fill(&mut self, xs: Range<usize>, ys: Range<usize>) {
+        for y in ys {
+            for x in xs.clone() {
+                self.set(x, y, true);
+            }
+        }
+    }
+
+    fn from_art(art: &[&str]) -> Self {
+        let mut glyph = Self::new(art[0].len());
+        for (y, row) in art.iter().enumerate() {
+            for (x, pixel) in row.bytes().enumerate() {
+                glyph.set(x, y, pixel == b'#');
+            }
+        }
+        glyph
+    }
+}
+
+pub(super) fn rasterize(c: char) -> Glyph {
+    if let Some(arms) = box_arms(c) {
+        return draw_box(arms, is_rounded(c));
+    }
+
+    let width = CELL_WIDTH * c.width().unwrap_or(1).clamp(1, 2);
+
+    match c {
+        ' ' => Glyph::new(width),
+        '█' => {
+            let mut glyph = Glyph::new(width);
+            glyph.fill(0..width, 0..CELL_HEIGHT);
+            glyph
+        }
+        '▀' => {
+            let mut glyph = Glyph::new(width);
+            glyph.fill(0..width, 0..CELL_HEIGHT / 2);
+            glyph
+        }
+        '•' => draw_disc(width, 2.5, 0.0),
+        '👌' => draw_disc(width, 6.5, 3.5),
+        '👆' => draw_finger(|x, y| (x, y)),
+        '👇' => draw_finger(|x, y| (x, CELL_HEIGHT - 1 - y)),
+        '👈' => draw_finger(|x, y| (y, x)),
+        '👉' => draw_finger(|x, y| (CELL_HEIGHT - 1 - y, x)),
+        'ඞ' => Glyph::from_art(&SUS),
+        _ => draw_missing(width),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// The lines making up an arm of some weight, as ranges across the arm.
+type Lines = &'static [(Range<usize>, Side)];
+
+/// Which side of an arm a line lies on, looking along the arm towards the centre.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Before,
+    Center,
+    After,
+}
+
+/// The weights of the up, down, left and right arms of a box-drawing character.
+fn box_arms(c: char) -> Option<[Weight; 4]> {
+    use Weight::{Double as D, Heavy as H, Light as L, None as N};
+
+    let arms = match c {
+        '│' => [L, L, N, N],
+        '─' => [N, N, L, L],
+        '┌' | '╭' => [N, L, N, L],
+        '┐' | '╮' => [N, L, L, N],
+        '└' | '╰' => [L, N, N, L],
+        '┘' | '╯' => [L, N, L, N],
+        '┃' => [H, H, N, N],
+        '━' => [N, N, H, H],
+        '┏' => [N, H, N, H],
+        '┓' => [N, H, H, N],
+        '┗' => [H, N, N, H],
+        '┛' => [H, N, H, N],
+        '╽' => [L, H, N, N],
+        '╿' => [H, L, N, N],
+        '╼' => [N, N, L, H],
+        '╾' => [N, N, H, L],
+        '┎' => [N, H, N, L],
+        '┒' => [N, H, L, N],
+        '┖' => [H, N, N, L],
+        '┚' => [H, N, L, N],
+        '║' => [D, D, N, N],
+        '═' => [N, N, D, D],
+        '╔' => [N, D, N, D],
+        '╗' => [N, D, D, N],
+        '╚' => [D, N, N, D],
+        '╝' => [D, N, D, N],
+        '┬' => [N, L, L, L],
+        '┴' => [L, N, L, L],
+        '├' => [L, L, N, L],
+        '┤' => [L, L, L, N],
+        '┼' => [L, L, L, L],
+        '┳' => [N, H, H, H],
+        '┻' => [H, N, H, H],
+        '┣' => [H, H, N, H],
+        '┫' => [H, H, H, N],
+        '╋' => [H, H, H, H],
+        '┰' => [N, H, L, L],
+        '┸' => [H, N, L, L],
+        '┠' => [H, H, N, L],
+        '┨' => [H, H, L, N],
+        '╂' => [H, H, L, L],
+        '╦' => [N, D, D, D],
+        '╩' => [D, N, D, D],
+        '╠' => [D, D, N, D],
+        '╣' => [D, D, D, N],
+        '╬' => [D, D, D, D],
+        '┿' => [L, L, H, H],
+        '╫' => [D, D, L, L],
+        '╪' => [L, L, D, D],
+        _ => return None,
+    };
+
+    Some(arms)
+}
+
+fn is_rounded(c: char) -> bool {
+    matches!(c, '╭' | '╮' | '╰' | '╯')
+}
+
+/// The columns covered by the lines of a vertical arm.
+fn vertical_lines(weight: Weight) -> Lines {
+    match weight {
+        Weight::None => &[],
+        Weight::Light => &[(3..5, Side::Center)],
+        Weight::Heavy => &[(2..6, Side::Center)],
+        Weight::Double => &[(1..3, Side::Before), (5..7, Side::After)],
+    }
+}
+
+/// The rows covered by the lines of a horizontal arm.
+fn horizontal_lines(weight: Weight) -> Lines {
+    match weight {
+        Weight::None => &[],
+        Weight::Light => &[(7..9, Side::Center)],
+        Weight::Heavy => &[(6..10, Side::Center)],
+        Weight::Double => &[(4..6, Side::Before), (10..12, Side::After)],
+    }
+}
+
+fn draw_box([up, down, left, right]: [Weight; 4], rounded: bool) -> Glyph {
+    let mut glyph = Glyph::new(CELL_WIDTH);
+
+    // Both axes are symmetric about the centre,
+    // so each arm is drawn as if it pointed down or right and then mirrored as needed.
+    for (weight, flip) in [(up, true), (down, false)] {
+        for (xs, side) in vertical_lines(weight) {
+            let start = arm_start(*side, left, right, CELL_HEIGHT / 2, horizontal_lines);
+            for y in start..CELL_HEIGHT {
+                let y = if flip { CELL_HEIGHT - 1 - y } else { y };
+                glyph.fill(xs.clone(), y..y + 1);
+            }
+        }
+    }
+
+    for (weight, flip) in [(left, true), (right, false)] {
+        for (ys, side) in horizontal_lines(weight) {
+            let start = arm_start(*side, up, down, CELL_WIDTH / 2, vertical_lines);
+            for x in start..CELL_WIDTH {
+                let x = if flip { CELL_WIDTH - 1 - x } else { x };
+                glyph.fill(x..x + 1, ys.clone());
+            }
+        }
+    }
+
+    if rounded {
+        let x = if right != Weight::None { 3 } else { 4 };
+        let y = if down != Weight::None { 7 } else { 8 };
+        glyph.set(x, y, false);
+    }
+
+    glyph
+}
+
+/// Determines where a line of an arm begins, measured from the far edge of the cell.
+///
+/// Lines meet the perpendicular arms so corners and junctions are closed:
+/// a line stops at the nearest perpendicular line on its own side if there is one,
+/// and otherwise reaches across to the farthest.
+fn arm_start(
+    side: Side,
+    before: Weight,
+    after: Weight,
+    center: usize,
+    perpendicular_lines: fn(Weight) -> Lines,
+) -> usize {
+    let all = perpendicular_lines(before)
+        .iter()
+        .chain(perpendicular_lines(after));
+    let Some(farthest) = all.map(|(range, _)| range.start).min() else {
+        return center;
+    };
+
+    let own_side = match side {
+        Side::Before => before,
+        Side::After => after,
+        Side::Center => return farthest,
+    };
+
+    perpendicular_lines(own_side)
+        .iter()
+        .map(|(range, _)| range.start)
+        .max()
+        .unwrap_or(farthest)
+}
+
+fn draw_disc(width: usize, outer_radius: f32, inner_radius: f32) -> Glyph {
+    let mut glyph = Glyph::new(width);
+    let center_x = width as f32 / 2.0 - 0.5;
+    let center_y = CELL_HEIGHT as f32 / 2.0 - 0.5;
+
+    for y in 0..CELL_HEIGHT {
+        for x in 0..width {
+            let distance = (x as f32 - center_x).hypot(y as f32 - center_y);
+            glyph.set(x, y, (inner_radius..=outer_radius).contains(&distance));
+        }
+    }
+
+    glyph
+}
+
+/// Draws an arrow pointing up in a square two cells wide,
+/// with `transform` rotating it into place.
+fn draw_finger(transform: impl Fn(usize, usize) -> (usize, usize)) -> Glyph {
+    let mut glyph = Glyph::new(CELL_WIDTH * 2);
+
+    for y in 0..CELL_HEIGHT {
+        for x in 0..CELL_HEIGHT {
+            let in_head = (2..8).contains(&y) && x.abs_diff(8) + 2 <= y;
+            let in_shaft = (8..14).contains(&y) && (6..10).contains(&x);
+            if in_head || in_shaft {
+                let (x, y) = transform(x, y);
+                glyph.set(x, y, true);
+            }
+        }
+    }
+
+    glyph
+}
+
+fn draw_missing(width: usize) -> Glyph {
+    let mut glyph = Glyph::new(width);
+    glyph.fill(1..width - 1, 2..3);
+    glyph.fill(1..width - 1, CELL_HEIGHT - 3..CELL_HEIGHT - 2);
+    glyph.fill(1..2, 2..CELL_HEIGHT - 2);
+    glyph.fill(width - 2..width - 1, 2..CELL_HEIGHT - 2);
+    glyph
+}
+
+const SUS: [&str; CELL_HEIGHT] = [
+    "........", "........", "...###..", "..#####.", ".##...##", ".##...##", ".#######", "########",
+    "########", "#######.", ".######.", ".##..##.", ".##..##.", "........", "........", "........",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::pipe::KindSet;
+
+    fn is_missing(c: char) -> bool {
+        let glyph = rasterize(c);
+        glyph.rows == draw_missing(glyph.width).rows
+    }
+
+    #[test]
+    fn every_pipe_has_a_glyph() {
+        let kinds: KindSet = "heavy,light,curved,knobby,emoji,outline,dots,blocks,sus"
+            .parse()
+            .unwrap();
+
+        for c in kinds.chars() {
+            assert!(!is_missing(c), "no glyph for {c:?}");
+        }
+    }
+
+    #[test]
+    fn missing_glyphs_match_their_width() {
+        assert!(is_missing('x'));
+        assert_eq!(rasterize('x').width(), CELL_WIDTH);
+
+        assert!(is_missing('🦀'));
+        assert_eq!(rasterize('🦀').width(), CELL_WIDTH * 2);
+    }
+}
@@ -0,0 +1,263 @@
+use super::font::{self, CELL_HEIGHT, CELL_WIDTH};
+use super::{BACKGROUND, FOREGROUND};
+use gif::{Encoder, Frame, Repeat};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::Duration;
+use terminal::{Backend, GridBackend};
+
+/// The largest screen, in columns and rows, whose frames fit in a GIF.
+pub(crate) const MAX_SIZE: (u16, u16) = (
+    (u16::MAX as usize / CELL_WIDTH) as u16,
+    (u16::MAX as usize / CELL_HEIGHT) as u16,
+);
+
+/// Draws to an in-memory grid, adding every finished frame to an animated GIF.
+pub struct GifBackend<W: Write> {
+    grid: GridBackend,
+    encoder: Encoder<W>,
+    delay: u16,
+}
+
+impl<W: Write> GifBackend<W> {
+    pub fn new(
+        output: W,
+        (width, height): (u16, u16),
+        frame_length: Duration,
+    ) -> anyhow::Result<Self> {
+        let pixels = |cells: u16, cell_size: usize| u16::try_from(cells as usize * cell_size).ok();
+        let (Some(pixel_width), Some(pixel_height)) =
+            (pixels(width, CELL_WIDTH), pixels(height, CELL_HEIGHT))
+        else {
+            let (max_width, max_height) = MAX_SIZE;
+            anyhow::bail!(
+                "a {width}×{height} screen is too large for a GIF, which fits at most {max_width}×{max_height}"
+            );
+        };
+
+        let mut encoder = Encoder::new(output, pixel_width, pixel_height, &[])?;
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        // GIF delays are in hundredths of a second,
+        // and most viewers slow down anything shorter than two
+        let delay = (frame_length.as_millis() / 10).clamp(2, u16::MAX as u128) as u16;
+
+        Ok(Self {
+            grid: GridBackend::new(width, height),
+            encoder,
+            delay,
+        })
+    }
+
+    fn write_frame(&mut self) -> anyhow::Result<()> {
+        let grid = self.grid.grid();
+        let width = grid.width() as usize * CELL_WIDTH;
+        let height = grid.height() as usize * CELL_HEIGHT;
+
+        let mut palette = Palette::default();
+        let background = palette.index_of(BACKGROUND);
+        let mut pixels = vec![background; width * height];
+        self.draw(width, |i, color| pixels[i] = palette.index_of(color));
+
+        let mut frame = match palette.into_bytes() {
+            Some(palette) => {
+                Frame::from_palette_pixels(width as u16, height as u16, pixels, palette, None)
+            }
+
+            // too many colors for one frame, so let the encoder pick the closest ones
+            None => {
+                let (r, g, b) = BACKGROUND;
+                let mut rgb = [r, g, b].repeat(width * height);
+                self.draw(width, |i, (r, g, b)| {
+                    rgb[i * 3..i * 3 + 3].copy_from_slice(&[r, g, b]);
+                });
+                Frame::from_rgb_speed(width as u16, height as u16, &rgb, 10)
+            }
+        };
+        frame.delay = self.delay;
+
+        self.encoder.write_frame(&frame)?;
+
+        Ok(())
+    }
+
+    /// Calls `plot` with the index and color of every foreground pixel in the frame.
+    fn draw(&self, width: usize, mut plot: impl FnMut(usize, (u8, u8, u8))) {
+        let grid = self.grid.grid();
+
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let Some(cell) = grid.get(x, y) else {
+                    continue;
+                };
+
+                let color = cell.color.map_or(FOREGROUND, |c| c.to_rgb());
+                let glyph = font::rasterize(cell.c);
+                let origin_x = x as usize * CELL_WIDTH;
+                let origin_y = y as usize * CELL_HEIGHT;
+
+                for glyph_y in 0..CELL_HEIGHT {
+                    for glyph_x in 0..glyph.width().min(width - origin_x) {
+                        if glyph.get(glyph_x, glyph_y) {
+                            plot((origin_y + glyph_y) * width + origin_x + glyph_x, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for GifBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.grid.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.grid.flush()
+    }
+}
+
+impl<W: Write> Backend for GifBackend<W> {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        self.grid.size()
+    }
+
+    fn end_frame(&mut self) -> anyhow::Result<()> {
+        self.write_frame()
+    }
+}
+
+/// The colors used in a single frame, up to the 256 a GIF frame allows.
+#[derive(Default)]
+struct Palette {
+    indices: HashMap<(u8, u8, u8), u8>,
+    colors: Vec<(u8, u8, u8)>,
+}
+
+impl Palette {
+    fn index_of(&mut self, color: (u8, u8, u8)) -> u8 {
+        if let Some(index) = self.indices.get(&color) {
+            return *index;
+        }
+
+        self.colors.push(color);
+        let index = (self.colors.len() - 1).min(255) as u8;
+        self.indices.insert(color, index);
+        index
+    }
+
+    fn into_bytes(self) -> Option<Vec<u8>> {
+        if self.colors.len() > 256 {
+            return None;
+        }
+
+        Some(
+            self.colors
+                .into_iter()
+                .flat_map(|(r, g, b)| [r, g, b])
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{App, Config};
+    use model::pipe::ColorMode;
+
+    const SIZE: (u16, u16) = (12, 5);
+    const FRAME_LENGTH: Duration = Duration::from_millis(50);
+
+    fn render(ticks: u32) -> Vec<u8> {
+        let mut output = Vec::new();
+        let config = Config {
+            color_mode: Some(ColorMode::Rgb),
+            num_pipes: Some(2),
+            seed: Some(1),
+            ..Config::default()
+        };
+
+        let backend = GifBackend::new(&mut output, SIZE, FRAME_LENGTH).unwrap();
+        let mut app = App::with_backend(config, backend).unwrap();
+        app.run_headless(Some(ticks), None).unwrap();
+        drop(app);
+
+        output
+    }
+
+    /// Returns the logical screen size and the number of frames.
+    fn decode(gif: &[u8]) -> ((u16, u16), usize) {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif).unwrap();
+
+        let size = (decoder.width(), decoder.height());
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 5);
+            frames += 1;
+        }
+
+        (size, frames)
+    }
+
+    #[test]
+    fn header_and_size() {
+        let gif = render(3);
+        assert_eq!(&gif[..6], b"GIF89a");
+
+        let (size, _) = decode(&gif);
+        assert_eq!(
+            size,
+            (SIZE.0 * CELL_WIDTH as u16, SIZE.1 * CELL_HEIGHT as u16)
+        );
+    }
+
+    #[test]
+    fn one_frame_per_tick() {
+        for ticks in [2, 3] {
+            let (_, frames) = decode(&render(ticks));
+            assert_eq!(frames, ticks as usize);
+        }
+    }
+
+    #[test]
+    fn oversized_screen() {
+        let (max_width, max_height) = MAX_SIZE;
+        assert!(GifBackend::new(io::sink(), MAX_SIZE, FRAME_LENGTH).is_ok());
+        assert!(GifBackend::new(io::sink(), (max_width + 1, 2), FRAME_LENGTH).is_err());
+        assert!(GifBackend::new(io::sink(), (2, max_height + 1), FRAME_LENGTH).is_err());
+        assert!(GifBackend::new(io::sink(), (9000, 2), FRAME_LENGTH).is_err());
+    }
+
+    #[test]
+    fn missing_glyphs() {
+        let mut output = Vec::new();
+        let mut backend = GifBackend::new(&mut output, SIZE, FRAME_LENGTH).unwrap();
+
+        // a wide glyph in the last column is cut off at the edge
+        write!(backend, "\x1b[1;1H🦀\x1b[2;3Hx\x1b[5;12H🦀").unwrap();
+        backend.flush().unwrap();
+        backend.end_frame().unwrap();
+        drop(backend);
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(&output[..]).unwrap();
+        let frame = decoder.read_next_frame().unwrap().unwrap();
+        let width = frame.width as usize;
+        let pixel = |x: usize, y: usize| {
+            let i = (y * width + x) * 4;
+            (frame.buffer[i], frame.buffer[i + 1], frame.buffer[i + 2])
+        };
+
+        // each is drawn as a box, starting one pixel in from the top left of its cell
+        for (column, row) in [(0, 0), (2, 1), (11, 4)] {
+            let (x, y) = (column * CELL_WIDTH, row * CELL_HEIGHT);
+            assert_eq!(pixel(x, y + 2), BACKGROUND);
+            assert_eq!(pixel(x + 1, y + 2), FOREGROUND);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use super::{ansi_class, escape, hex, runs, ANSI_COLORS, BACKGROUND, FOREGROUND};
+use std::fmt::Write;
+use terminal::Grid;
+
+pub(super) fn render(grid: &Grid) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>pipes-rs</title>\n<style>\n");
+    writeln!(
+        html,
+        "pre {{ font-family: ui-monospace, Menlo, Consolas, monospace; line-height: 1.2; background: {}; color: {}; padding: 1em; display: inline-block; }}",
+        hex(BACKGROUND),
+        hex(FOREGROUND)
+    )
+    .unwrap();
+    html.push_str(".bold { font-weight: bold; }\n");
+    for color in ANSI_COLORS {
+        let class = ansi_class(color).unwrap();
+        writeln!(html, ".{class} {{ color: {}; }}", hex(color.to_rgb())).unwrap();
+    }
+    html.push_str("</style>\n</head>\n<body>\n<pre>");
+
+    for y in 0..grid.height() {
+        let mut column = 0;
+
+        for run in runs(grid, y) {
+            html.push_str(&" ".repeat((run.x - column) as usize));
+            column = run.x + unicode_width::UnicodeWidthStr::width(run.text.as_str()) as u16;
+
+            let (classes, rgb) = run.style();
+            html.push_str("<span");
+            if !classes.is_empty() {
+                write!(html, r#" class="{classes}""#).unwrap();
+            }
+            if let Some(rgb) = rgb {
+                write!(html, r#" style="color: {rgb}""#).unwrap();
+            }
+            write!(html, ">{}</span>", escape(&run.text)).unwrap();
+        }
+
+        html.push('\n');
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+
+    html
+}
@@ -0,0 +1,66 @@
+use super::{ansi_class, escape, hex, runs, ANSI_COLORS, BACKGROUND, FOREGROUND};
+use std::fmt::Write;
+use terminal::Grid;
+
+const CELL_WIDTH: f32 = 9.0;
+const CELL_HEIGHT: f32 = 18.0;
+const FONT_SIZE: f32 = 15.0;
+
+pub(super) fn render(grid: &Grid) -> String {
+    let width = grid.width() as f32 * CELL_WIDTH;
+    let height = grid.height() as f32 * CELL_HEIGHT;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+
+    svg.push_str("<style>\n");
+    writeln!(
+        svg,
+        "text {{ font-family: ui-monospace, Menlo, Consolas, monospace; font-size: {FONT_SIZE}px; white-space: pre; fill: {}; }}",
+        hex(FOREGROUND)
+    )
+    .unwrap();
+    svg.push_str(".bold { font-weight: bold; }\n");
+    for color in ANSI_COLORS {
+        let class = ansi_class(color).unwrap();
+        writeln!(svg, ".{class} {{ fill: {}; }}", hex(color.to_rgb())).unwrap();
+    }
+    svg.push_str("</style>\n");
+
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex(BACKGROUND)
+    )
+    .unwrap();
+
+    for y in 0..grid.height() {
+        // place the baseline a little above the bottom of the row to leave room for descenders
+        let baseline = (y + 1) as f32 * CELL_HEIGHT - CELL_HEIGHT * 0.25;
+
+        for run in runs(grid, y) {
+            let (classes, rgb) = run.style();
+            write!(
+                svg,
+                r#"<text x="{}" y="{baseline}""#,
+                run.x as f32 * CELL_WIDTH
+            )
+            .unwrap();
+            if !classes.is_empty() {
+                write!(svg, r#" class="{classes}""#).unwrap();
+            }
+            if let Some(rgb) = rgb {
+                write!(svg, r#" fill="{rgb}""#).unwrap();
+            }
+            writeln!(svg, ">{}</text>", escape(&run.text)).unwrap();
+        }
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
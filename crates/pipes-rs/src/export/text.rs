@@ -0,0 +1,36 @@
+use super::runs;
+use crossterm::style::{Attribute, SetAttribute, SetForegroundColor};
+use std::fmt::Write;
+use terminal::Grid;
+
+/// Renders the grid as lines of text,
+/// styled with escape sequences if `styled` is set.
+pub(crate) fn render(grid: &Grid, styled: bool) -> String {
+    let mut text = String::new();
+
+    for y in 0..grid.height() {
+        let mut column = 0;
+
+        for run in runs(grid, y) {
+            text.push_str(&" ".repeat((run.x - column) as usize));
+            column = run.x + unicode_width::UnicodeWidthStr::width(run.text.as_str()) as u16;
+
+            if !styled {
+                text.push_str(&run.text);
+                continue;
+            }
+
+            if let Some(color) = run.color {
+                write!(text, "{}", SetForegroundColor(color.into())).unwrap();
+            }
+            if run.bold {
+                write!(text, "{}", SetAttribute(Attribute::Bold)).unwrap();
+            }
+            write!(text, "{}{}", run.text, SetAttribute(Attribute::Reset)).unwrap();
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
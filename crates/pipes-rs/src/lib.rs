@@ -1,32 +1,85 @@
 mod config;
+mod export;
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod test_common;
 pub use config::Config;
+pub use export::GifBackend;
 
-use model::pipe::{KindSet, Pipe};
+use model::pipe::{Avoidance, Background, ColorMode, Connections, KindSet, Palette, Pipe};
 use model::position::InScreenBounds;
-use std::{io, thread, time};
-use terminal::{Event, Terminal};
+use model::rngs::Rngs;
+use std::path::{Path, PathBuf};
+use std::{thread, time};
+use terminal::{Backend, CrosstermBackend, Event, Grid, GridBackend, Terminal};
 
-pub struct App {
-    terminal: Terminal,
+const TOO_SMALL_MESSAGE: &str = "terminal too small";
+
+/// How often to check for input while the terminal is too small,
+/// since nothing is being drawn to set the pace.
+const TOO_SMALL_POLL_INTERVAL: time::Duration = time::Duration::from_millis(50);
+
+pub struct App<B: Backend = CrosstermBackend> {
+    terminal: Terminal<B>,
     config: Config,
+    color_mode: ColorMode,
+    palette: Option<Palette>,
+    background: Option<Background>,
     kinds: KindSet,
+    connections: Connections,
+    /// Exports that failed while running,
+    /// which are reported on exit rather than interrupting the animation.
+    failed_exports: Vec<anyhow::Error>,
+    rngs: Rngs,
+    seed: u64,
 }
 
 impl App {
     pub fn new(config: Config) -> anyhow::Result<Self> {
+        Self::with_backend(config, CrosstermBackend::new())
+    }
+}
+
+impl<B: Backend> App<B> {
+    pub fn with_backend(config: Config, backend: B) -> anyhow::Result<Self> {
         let kinds = config.kinds();
+        let color_mode = config.color_mode().resolve();
+        let palette = config.palette;
+
+        let seed = config.seed.unwrap_or_else(rng::seed_from_time);
+        let rngs = Rngs::new(
+            config.movement_seed.unwrap_or(seed),
+            config.color_seed.unwrap_or(seed),
+            config.kind_seed.unwrap_or(seed),
+        );
 
-        let stdout = io::stdout().lock();
         let largest_custom_width = kinds.custom_widths().max();
-        let terminal = Terminal::new(stdout, kinds.chars(), largest_custom_width)?;
+        let terminal = Terminal::new(backend, kinds.chars(), largest_custom_width)?;
+        let connections = Connections::new(terminal.size());
 
         Ok(Self {
             terminal,
             config,
+            color_mode,
+            palette,
+            background: None,
             kinds,
+            connections,
+            failed_exports: Vec::new(),
+            rngs,
+            seed,
         })
     }
 
+    /// The seed used for this run, which can be passed to `--seed` to replay it.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
     pub fn run(mut self) -> anyhow::Result<()> {
         self.terminal.enter_alternate_screen()?;
         self.terminal.set_raw_mode(true)?;
@@ -34,6 +87,19 @@ impl App {
         if self.config.bold() {
             self.terminal.enable_bold()?;
         }
+        if self.config.screensaver() {
+            self.terminal.enable_screensaver_mode()?;
+        }
+        let synchronized_output = match self.config.sync_output {
+            Some(enabled) => enabled,
+            None => self.terminal.supports_synchronized_output()?,
+        };
+        self.terminal.set_synchronized_output(synchronized_output);
+        self.detect_background()?;
+
+        if let ControlFlow::Break = self.wait_while_too_small()? {
+            return self.terminal.restore();
+        }
 
         let mut pipes = self.create_pipes();
 
@@ -43,20 +109,57 @@ impl App {
             }
         }
 
-        self.terminal.set_raw_mode(false)?;
-        self.terminal.set_cursor_visibility(true)?;
-        self.terminal.leave_alternate_screen()?;
+        self.terminal.restore()?;
+
+        for error in &self.failed_exports {
+            eprintln!("error: {error:#}");
+        }
 
         Ok(())
     }
 
-    pub fn reset_loop(&mut self, pipes: &mut Vec<Pipe>) -> anyhow::Result<ControlFlow> {
-        self.terminal.clear()?;
+    /// Runs as fast as possible until either the given number of ticks have passed
+    /// or the given portion of the screen is covered,
+    /// without touching the terminal’s modes or reading any input.
+    ///
+    /// Resets happen just as they would when running interactively,
+    /// so a seed produces the same picture either way.
+    pub fn run_headless(
+        &mut self,
+        ticks: Option<u32>,
+        coverage: Option<f32>,
+    ) -> anyhow::Result<()> {
+        if self.terminal.is_too_small() {
+            anyhow::bail!("the screen is too small to fit any pipes of the chosen kinds");
+        }
+
+        self.terminal.set_cursor_visibility(false)?;
+        if self.config.bold() {
+            self.terminal.enable_bold()?;
+        }
+
+        let mut pipes = self.create_pipes();
+        self.reset(&mut pipes)?;
+
+        for _ in 0..ticks.unwrap_or(u32::MAX) {
+            if coverage.is_some_and(|c| self.terminal.portion_covered() >= c) {
+                break;
+            }
+
+            if !self.under_threshold() {
+                self.reset(&mut pipes)?;
+            }
 
-        for pipe in &mut *pipes {
-            *pipe = self.create_pipe();
+            self.tick(&mut pipes)?;
+            self.terminal.flush()?;
         }
 
+        Ok(())
+    }
+
+    pub fn reset_loop(&mut self, pipes: &mut Vec<Pipe>) -> anyhow::Result<ControlFlow> {
+        self.reset(pipes)?;
+
         while self.under_threshold() {
             let control_flow = self.tick_loop(pipes)?;
             match control_flow {
@@ -74,14 +177,19 @@ impl App {
         match self.terminal.get_event()? {
             Some(Event::Exit) => return Ok(ControlFlow::Break),
             Some(Event::Reset) => return Ok(ControlFlow::Reset),
+            Some(Event::Export) => {
+                if let Err(error) = self.export(&self.interactive_export_path()) {
+                    self.failed_exports.push(error);
+                }
+            }
+            Some(Event::Resize) if self.terminal.is_too_small() => {
+                return self.wait_while_too_small();
+            }
+            Some(Event::Resize) => self.respawn_offscreen(pipes),
             None => {}
         }
 
-        for pipe in pipes {
-            self.render_pipe(pipe)?;
-            self.tick_pipe(pipe);
-        }
-
+        self.tick(pipes)?;
         self.terminal.flush()?;
 
         let tick_length_so_far = start_time.elapsed();
@@ -96,22 +204,169 @@ impl App {
         Ok(ControlFlow::Continue)
     }
 
-    fn tick_pipe(&mut self, pipe: &mut Pipe) {
+    /// Shows a message for as long as the terminal is too small to draw anything in,
+    /// returning [`ControlFlow::Reset`] once it has grown.
+    fn wait_while_too_small(&mut self) -> anyhow::Result<ControlFlow> {
+        if !self.terminal.is_too_small() {
+            return Ok(ControlFlow::Continue);
+        }
+
+        self.terminal.show_message(TOO_SMALL_MESSAGE)?;
+
+        while self.terminal.is_too_small() {
+            match self.terminal.get_event()? {
+                Some(Event::Exit) => return Ok(ControlFlow::Break),
+                // Once it has grown, the reset that follows clears the message.
+                Some(Event::Resize) if self.terminal.is_too_small() => {
+                    self.terminal.show_message(TOO_SMALL_MESSAGE)?;
+                }
+                Some(Event::Resize | Event::Reset | Event::Export) | None => {}
+            }
+
+            thread::sleep(TOO_SMALL_POLL_INTERVAL);
+        }
+
+        Ok(ControlFlow::Reset)
+    }
+
+    /// Saves what is currently on screen as an SVG or HTML file,
+    /// depending on the extension of `path`.
+    pub fn export(&self, path: &Path) -> anyhow::Result<()> {
+        export::export(&self.terminal.grid(), path)
+    }
+
+    /// Returns what is currently on screen as text,
+    /// using escape sequences for color and bold when `styled` is set.
+    pub fn screen_text(&self, styled: bool) -> String {
+        export::text(&self.terminal.grid(), styled)
+    }
+
+    fn interactive_export_path(&self) -> PathBuf {
+        if let Some(path) = &self.config.export {
+            return path.clone();
+        }
+
+        let timestamp = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        PathBuf::from(format!("pipes-rs-{timestamp}.svg"))
+    }
+
+    fn reset(&mut self, pipes: &mut Vec<Pipe>) -> anyhow::Result<()> {
+        self.terminal.clear()?;
+        self.connections = Connections::new(self.terminal.size());
+        *pipes = self.create_pipes();
+
+        Ok(())
+    }
+
+    pub fn tick(&mut self, pipes: &mut Vec<Pipe>) -> anyhow::Result<()> {
+        let mut branches = Vec::new();
+        let mut i = 0;
+
+        while i < pipes.len() {
+            self.render_pipe(&pipes[i])?;
+
+            if let Some(branch) = self.branch(&pipes[i], pipes.len() + branches.len()) {
+                branches.push(branch);
+            }
+
+            if self.tick_pipe(&mut pipes[i]) {
+                i += 1;
+            } else {
+                pipes.remove(i);
+            }
+        }
+
+        // Branches start moving on the next tick,
+        // from the cell they forked off at.
+        pipes.append(&mut branches);
+
+        Ok(())
+    }
+
+    /// Moves the pipe on, returning whether it is still going.
+    fn tick_pipe(&mut self, pipe: &mut Pipe) -> bool {
+        let terminal = &self.terminal;
+        let avoidance = Avoidance {
+            strength: self.config.avoidance(),
+            is_occupied: &|x, y| terminal.is_covered(x, y),
+        };
+
         let InScreenBounds(stayed_onscreen) = pipe.tick(
-            self.terminal.size(),
+            terminal.size(),
             self.config.turn_chance(),
             self.config.rainbow(),
+            self.config.edge_mode(),
+            avoidance,
+            &mut self.rngs.movement,
         );
 
         if !stayed_onscreen {
-            *pipe = if self.config.inherit_style() {
-                pipe.dup(self.terminal.size())
-            } else {
-                self.create_pipe()
-            };
+            if pipe.is_branch() {
+                return false;
+            }
+            self.respawn(pipe);
+        }
+
+        true
+    }
+
+    /// Maybe forks a branch off `pipe`,
+    /// as long as there are fewer than the maximum number of pipes.
+    fn branch(&mut self, pipe: &Pipe, num_pipes: usize) -> Option<Pipe> {
+        let branch_chance = self.config.branch_chance();
+        if branch_chance <= 0.0 || num_pipes >= self.config.max_pipes() as usize {
+            return None;
+        }
+
+        if !self.rngs.movement.gen_bool(branch_chance) {
+            return None;
+        }
+
+        let mut branch = pipe.branch(
+            self.terminal.size(),
+            self.config.edge_mode(),
+            &mut self.rngs.movement,
+        )?;
+
+        if !self.config.inherit_branch_color() {
+            branch.recolor(
+                self.color_mode,
+                self.palette,
+                self.background,
+                &mut self.rngs.color,
+            );
+        }
+
+        Some(branch)
+    }
+
+    /// Respawns pipes left outside the screen after it shrank,
+    /// dropping any branches there.
+    fn respawn_offscreen(&mut self, pipes: &mut Vec<Pipe>) {
+        let (width, height) = self.terminal.size();
+        self.connections.resize((width, height));
+
+        let is_offscreen = |pipe: &Pipe| pipe.position.x >= width || pipe.position.y >= height;
+        pipes.retain(|pipe| !(pipe.is_branch() && is_offscreen(pipe)));
+
+        for pipe in pipes {
+            if is_offscreen(pipe) {
+                self.respawn(pipe);
+            }
         }
     }
 
+    fn respawn(&mut self, pipe: &mut Pipe) {
+        *pipe = if self.config.inherit_style() {
+            pipe.dup(self.terminal.size(), &mut self.rngs.movement)
+        } else {
+            self.create_pipe()
+        };
+    }
+
     fn render_pipe(&mut self, pipe: &Pipe) -> anyhow::Result<()> {
         self.terminal
             .move_cursor_to(pipe.position.x, pipe.position.y)?;
@@ -120,8 +375,9 @@ impl App {
             self.terminal.set_text_color(color.terminal)?;
         }
 
-        self.terminal.print(if rng::gen_bool(0.99999) {
-            pipe.to_char()
+        let c = self.connections.join(pipe, self.config.junctions());
+        self.terminal.print(if self.rngs.kind.gen_bool(0.99999) {
+            c
         } else {
             '🦀'
         })?;
@@ -136,16 +392,42 @@ impl App {
     }
 
     fn create_pipe(&mut self) -> Pipe {
-        let kind = self.kinds.choose_random();
+        let kind = self.kinds.choose_random(&mut self.rngs.kind);
 
         Pipe::new(
             self.terminal.size(),
-            self.config.color_mode(),
-            self.config.palette(),
+            self.color_mode,
+            self.palette,
+            self.background,
             kind,
+            &mut self.rngs,
         )
     }
 
+    /// Asks the terminal for its background color
+    /// so pipes can be kept readable against it,
+    /// choosing a palette to suit it if none was given.
+    fn detect_background(&mut self) -> anyhow::Result<()> {
+        let uses_palette = match self.color_mode {
+            ColorMode::Ansi => self.palette.is_some(),
+            ColorMode::None => false,
+            _ => true,
+        };
+        if !uses_palette {
+            return Ok(());
+        }
+
+        let Some(color) = self.terminal.background_color()? else {
+            return Ok(());
+        };
+
+        let background = Background::new(color);
+        self.palette.get_or_insert(background.palette());
+        self.background = Some(background);
+
+        Ok(())
+    }
+
     fn under_threshold(&self) -> bool {
         match self.config.reset_threshold() {
             Some(reset_threshold) => self.terminal.portion_covered() < reset_threshold,
@@ -154,9 +436,47 @@ impl App {
     }
 }
 
+/// Runs `ticks` ticks of the simulation on an in-memory terminal of the given size,
+/// returning what ends up on screen.
+pub fn simulate(config: Config, (width, height): (u16, u16), ticks: u32) -> anyhow::Result<Grid> {
+    let mut app = App::with_backend(config, GridBackend::new(width, height))?;
+    app.run_headless(Some(ticks), None)?;
+
+    Ok(app.backend().grid().clone())
+}
+
 #[must_use]
 pub enum ControlFlow {
     Continue,
     Break,
     Reset,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branching_stops_at_max_pipes() {
+        let config = Config {
+            color_mode: Some(ColorMode::None),
+            reset_threshold: Some(0.0),
+            num_pipes: Some(2),
+            max_pipes: Some(5),
+            branch_chance: Some(0.5),
+            seed: Some(1),
+            ..Config::default()
+        };
+        let mut app = App::with_backend(config, GridBackend::new(24, 10)).unwrap();
+        let mut pipes = app.create_pipes();
+
+        let mut most = 0;
+        for _ in 0..1000 {
+            app.tick(&mut pipes).unwrap();
+            assert!(pipes.len() <= 5, "{} pipes", pipes.len());
+            most = most.max(pipes.len());
+        }
+
+        assert_eq!(most, 5);
+    }
+}
@@ -1,7 +1,12 @@
+use anyhow::Context;
 use mimalloc::MiMalloc;
-use model::pipe::{ColorMode, Palette};
-use pipes_rs::{App, Config};
+use model::pipe::{ColorMode, EdgeMode, JunctionStyle, Palette};
+use pipes_rs::{App, Config, GifBackend};
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
 use std::{env, process};
+use terminal::{Backend, CrosstermBackend, Recorder, WriteBackend};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -11,12 +16,100 @@ fn main() -> anyhow::Result<()> {
     parse_args(&mut config);
     config.validate()?;
 
-    let app = App::new(config)?;
-    app.run()?;
+    let print_seed = config.print_seed();
+    let seed = if config.is_headless() {
+        run_headless(config)?
+    } else {
+        run(config)?
+    };
+
+    if print_seed {
+        eprintln!("seed: {seed}");
+    }
 
     Ok(())
 }
 
+fn run(config: Config) -> anyhow::Result<u64> {
+    // Animating into a file or pipe would never end.
+    if !io::stdout().is_terminal() {
+        anyhow::bail!(
+            "stdout isn’t a terminal; pass --ticks or --coverage along with --print to draw a single frame to it"
+        );
+    }
+
+    let mut backend: Box<dyn Backend> = Box::new(CrosstermBackend::new());
+
+    if let Some(path) = &config.record {
+        let frame_length = config.tick_length();
+        backend = Box::new(Recorder::new(backend, create_file(path)?, frame_length)?);
+    }
+
+    run_app(App::with_backend(config, backend)?)
+}
+
+fn run_app<B: Backend>(app: App<B>) -> anyhow::Result<u64> {
+    let seed = app.seed();
+    app.run()?;
+    terminal::resend_received_signal()?;
+
+    Ok(seed)
+}
+
+fn run_headless(mut config: Config) -> anyhow::Result<u64> {
+    // Unless the result is printed, there is no terminal to limit the colors.
+    if matches!(config.color_mode(), ColorMode::Auto) && !config.print() {
+        config.color_mode = Some(ColorMode::Rgb);
+    }
+
+    let size = config.size();
+    let frame_length = config.tick_length();
+
+    let mut backend: Box<dyn Backend> = match &config.render {
+        Some(path) => Box::new(GifBackend::new(create_file(path)?, size, frame_length)?),
+        None => Box::new(WriteBackend::new(io::sink(), size)),
+    };
+
+    if let Some(path) = &config.record {
+        backend = Box::new(Recorder::new(backend, create_file(path)?, frame_length)?);
+    }
+
+    let (ticks, coverage) = (config.ticks, config.coverage);
+    let export = config.export.clone();
+    let print = config.print();
+
+    let mut app = App::with_backend(config, backend)?;
+    app.run_headless(ticks, coverage)?;
+
+    if let Some(path) = export {
+        app.export(&path)?;
+    }
+
+    if print {
+        let styled = io::stdout().is_terminal();
+        match print_screen(&app.screen_text(styled)) {
+            // Whatever was reading has seen all it wanted to.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result.context("failed to print the screen")?,
+        }
+    }
+
+    Ok(app.seed())
+}
+
+fn print_screen(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+fn create_file(path: &Path) -> anyhow::Result<BufWriter<File>> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create “{}”", path.display()))?;
+
+    Ok(BufWriter::new(file))
+}
+
 fn parse_args(config: &mut Config) {
     let args: Vec<_> = env::args().skip(1).collect();
     let mut args_i = args.iter();
@@ -67,10 +160,12 @@ fn parse_args(config: &mut Config) {
         match option {
             "--color-mode" | "-c" => {
                 config.color_mode = match value {
+                    "auto" => Some(ColorMode::Auto),
                     "ansi" => Some(ColorMode::Ansi),
+                    "ansi256" => Some(ColorMode::Ansi256),
                     "rgb" => Some(ColorMode::Rgb),
                     "none" => Some(ColorMode::None),
-                    _ => invalid_value(option, value, "“ansi”, “rgb” or “none”"),
+                    _ => invalid_value(option, value, "“auto”, “ansi”, “ansi256”, “rgb” or “none”"),
                 }
             }
 
@@ -133,6 +228,15 @@ fn parse_args(config: &mut Config) {
                 }
             }
 
+            "--edge-mode" => {
+                config.edge_mode = match value {
+                    "respawn" => Some(EdgeMode::Respawn),
+                    "wrap" => Some(EdgeMode::Wrap),
+                    "bounce" => Some(EdgeMode::Bounce),
+                    _ => invalid_value(option, value, "“respawn”, “wrap” or “bounce”"),
+                }
+            }
+
             "--pipe-num" | "-p" => {
                 config.num_pipes = match value.parse() {
                     Ok(v) => Some(v),
@@ -147,6 +251,129 @@ fn parse_args(config: &mut Config) {
                 }
             }
 
+            "--branch-chance" => {
+                config.branch_chance = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a number"),
+                }
+            }
+
+            "--inherit-branch-color" => {
+                config.inherit_branch_color = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "“true” or “false”"),
+                }
+            }
+
+            "--max-pipes" => {
+                config.max_pipes = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--avoidance" => {
+                config.avoidance = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a number"),
+                }
+            }
+
+            "--junctions" => {
+                config.junctions = match value {
+                    "join" => Some(JunctionStyle::Join),
+                    "bridge" => Some(JunctionStyle::Bridge),
+                    "overwrite" => Some(JunctionStyle::Overwrite),
+                    _ => invalid_value(option, value, "“join”, “bridge” or “overwrite”"),
+                }
+            }
+
+            "--screensaver" => {
+                config.screensaver = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "“true” or “false”"),
+                }
+            }
+
+            "--sync-output" => {
+                config.sync_output = match value {
+                    "auto" => None,
+                    _ => match value.parse() {
+                        Ok(v) => Some(v),
+                        Err(_) => invalid_value(option, value, "“auto”, “true” or “false”"),
+                    },
+                }
+            }
+
+            "--seed" | "-s" => {
+                config.seed = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--movement-seed" => {
+                config.movement_seed = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--color-seed" => {
+                config.color_seed = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--kind-seed" => {
+                config.kind_seed = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--record" => config.record = Some(value.into()),
+
+            "--render" => config.render = Some(value.into()),
+
+            "--export" => config.export = Some(value.into()),
+
+            "--print" => {
+                config.print = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "“true” or “false”"),
+                }
+            }
+
+            "--coverage" => {
+                config.coverage = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a number"),
+                }
+            }
+
+            "--ticks" => {
+                config.ticks = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "a positive integer"),
+                }
+            }
+
+            "--size" => {
+                config.size = match parse_size(value) {
+                    Some(v) => Some(v),
+                    None => invalid_value(option, value, "a size such as “80x24”"),
+                }
+            }
+
+            "--print-seed" => {
+                config.print_seed = match value.parse() {
+                    Ok(v) => Some(v),
+                    Err(_) => invalid_value(option, value, "“true” or “false”"),
+                }
+            }
+
             _ => {
                 eprintln!("error: unrecognized option {option}");
                 eprintln!("see --help");
@@ -156,6 +383,11 @@ fn parse_args(config: &mut Config) {
     }
 }
 
+fn parse_size(s: &str) -> Option<(u16, u16)> {
+    let (width, height) = s.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
 fn required_value(option: &str) -> ! {
     eprintln!("error: a value is required for {option} but none was supplied");
     eprintln!("see --help");
@@ -0,0 +1,26 @@
+//! Helpers shared by the integration tests and the crate’s own unit tests.
+
+use std::path::PathBuf;
+use std::{env, fs};
+
+/// Compares `actual` against the golden file `tests/snapshots/{file_name}`,
+/// or rewrites the file when `UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot_file(file_name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(file_name);
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!("missing snapshot {file_name}; run with UPDATE_SNAPSHOTS=1 to create it")
+    });
+
+    assert!(
+        expected == actual,
+        "snapshot {file_name} does not match\n\nexpected:\n{expected}\nactual:\n{actual}"
+    );
+}
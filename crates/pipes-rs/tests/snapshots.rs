@@ -0,0 +1,362 @@
+//! Golden-frame tests that run the simulation headlessly
+//! and compare what ends up on screen against the files in `tests/snapshots`.
+//!
+//! After an intentional change to rendering,
+//! run with `UPDATE_SNAPSHOTS=1` to rewrite the expected output.
+
+mod common;
+
+use model::pipe::{ColorMode, EdgeMode, JunctionStyle, Kind, KindSet, Palette};
+use pipes_rs::Config;
+use std::fmt::Write;
+use terminal::{Color, Grid};
+
+const SIZE: (u16, u16) = (24, 10);
+const TICKS: u32 = 60;
+
+fn config() -> Config {
+    Config {
+        color_mode: Some(ColorMode::None),
+        reset_threshold: Some(0.0),
+        num_pipes: Some(3),
+        seed: Some(1),
+        ..Config::default()
+    }
+}
+
+fn assert_snapshot(name: &str, config: Config, size: (u16, u16), ticks: u32) {
+    let grid = pipes_rs::simulate(config, size, ticks).unwrap();
+    common::assert_snapshot_file(&format!("{name}.txt"), &render(&grid));
+}
+
+/// Renders the characters of the grid, framed so trailing blank columns stay visible,
+/// followed by the style of every non-empty cell.
+fn render(grid: &Grid) -> String {
+    let mut s = String::new();
+
+    let border = "-".repeat(grid.width() as usize);
+    writeln!(s, "+{border}+").unwrap();
+    for line in grid.to_string().lines() {
+        let padding = grid.width() as usize - unicode_width::UnicodeWidthStr::width(line);
+        writeln!(s, "|{line}{}|", " ".repeat(padding)).unwrap();
+    }
+    writeln!(s, "+{border}+").unwrap();
+
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let Some(cell) = grid.get(x, y) else {
+                continue;
+            };
+
+            let color = match cell.color {
+                Some(Color::Rgb { r, g, b }) => format!("#{r:02x}{g:02x}{b:02x}"),
+                Some(color) => format!("{color:?}"),
+                None => "default".to_string(),
+            };
+            let bold = if cell.bold { " bold" } else { "" };
+
+            writeln!(s, "{x},{y} {} {color}{bold}", cell.c).unwrap();
+        }
+    }
+
+    s
+}
+
+macro_rules! kind_tests {
+    ($($name:ident => $kind:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let config = Config {
+                    kinds: Some(KindSet::from_one($kind)),
+                    ..config()
+                };
+                assert_snapshot(concat!("kind_", stringify!($name)), config, SIZE, TICKS);
+            }
+        )*
+    };
+}
+
+kind_tests! {
+    heavy => Kind::Heavy,
+    light => Kind::Light,
+    curved => Kind::Curved,
+    knobby => Kind::Knobby,
+    emoji => Kind::Emoji,
+    outline => Kind::Outline,
+    dots => Kind::Dots,
+    blocks => Kind::Blocks,
+    sus => Kind::Sus,
+}
+
+macro_rules! palette_tests {
+    ($($name:ident => $palette:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let config = Config {
+                    color_mode: Some(ColorMode::Rgb),
+                    palette: Some($palette),
+                    ..config()
+                };
+                assert_snapshot(concat!("palette_", stringify!($name)), config, SIZE, TICKS);
+            }
+        )*
+    };
+}
+
+palette_tests! {
+    default => Palette::Default,
+    darker => Palette::Darker,
+    pastel => Palette::Pastel,
+    matrix => Palette::Matrix,
+}
+
+#[test]
+fn color_mode_ansi() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        ..config()
+    };
+    assert_snapshot("color_mode_ansi", config, SIZE, TICKS);
+}
+
+#[test]
+fn color_mode_ansi256() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi256),
+        rainbow: Some(15),
+        ..config()
+    };
+    assert_snapshot("color_mode_ansi256", config, SIZE, TICKS);
+}
+
+#[test]
+fn color_mode_ansi_with_palette() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        palette: Some(Palette::Matrix),
+        ..config()
+    };
+    assert_snapshot("color_mode_ansi_with_palette", config, SIZE, TICKS);
+}
+
+#[test]
+fn rainbow() {
+    let config = Config {
+        color_mode: Some(ColorMode::Rgb),
+        rainbow: Some(15),
+        ..config()
+    };
+    assert_snapshot("rainbow", config, SIZE, TICKS);
+}
+
+#[test]
+fn not_bold() {
+    let config = Config {
+        bold: Some(false),
+        ..config()
+    };
+    assert_snapshot("not_bold", config, SIZE, 20);
+}
+
+// With no turns every pipe runs straight off an edge,
+// so a small screen exercises exits through all four sides.
+#[test]
+fn edge_exit_respawn() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        num_pipes: Some(4),
+        turn_chance: Some(0.0),
+        ..config()
+    };
+    assert_snapshot("edge_exit_respawn", config, (12, 6), 40);
+}
+
+#[test]
+fn edge_exit_inherit_style() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        num_pipes: Some(4),
+        turn_chance: Some(0.0),
+        inherit_style: Some(true),
+        ..config()
+    };
+    assert_snapshot("edge_exit_inherit_style", config, (12, 6), 40);
+}
+
+#[test]
+fn edge_wrap() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        num_pipes: Some(2),
+        turn_chance: Some(0.2),
+        edge_mode: Some(EdgeMode::Wrap),
+        ..config()
+    };
+    assert_snapshot("edge_wrap", config, (12, 6), 40);
+}
+
+#[test]
+fn edge_bounce() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        num_pipes: Some(2),
+        turn_chance: Some(0.05),
+        edge_mode: Some(EdgeMode::Bounce),
+        ..config()
+    };
+    assert_snapshot("edge_bounce", config, (12, 6), 40);
+}
+
+#[test]
+fn edge_exit_mixed_kinds() {
+    let config = Config {
+        kinds: Some("heavy,curved,outline".parse().unwrap()),
+        turn_chance: Some(0.5),
+        ..config()
+    };
+    assert_snapshot("edge_exit_mixed_kinds", config, (8, 4), 60);
+}
+
+#[test]
+fn avoidance() {
+    let config = Config {
+        num_pipes: Some(6),
+        turn_chance: Some(0.3),
+        avoidance: Some(1.0),
+        ..config()
+    };
+    assert_snapshot("avoidance", config, SIZE, TICKS);
+}
+
+#[test]
+fn avoidance_covers_more() {
+    let covered = |avoidance| {
+        let config = Config {
+            num_pipes: Some(6),
+            turn_chance: Some(0.3),
+            avoidance: Some(avoidance),
+            ..config()
+        };
+        let grid = pipes_rs::simulate(config, SIZE, TICKS).unwrap();
+
+        let mut covered = 0;
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                covered += usize::from(grid.get(x, y).is_some());
+            }
+        }
+        covered
+    };
+
+    let (without, with) = (covered(0.0), covered(1.0));
+    assert!(
+        with > without,
+        "{with} cells covered with avoidance, {without} without"
+    );
+}
+
+#[test]
+fn junctions_overwrite() {
+    let config = Config {
+        junctions: Some(JunctionStyle::Overwrite),
+        ..config()
+    };
+    assert_snapshot("junctions_overwrite", config, SIZE, TICKS);
+}
+
+#[test]
+fn junctions_bridge() {
+    let config = Config {
+        junctions: Some(JunctionStyle::Bridge),
+        ..config()
+    };
+    assert_snapshot("junctions_bridge", config, SIZE, TICKS);
+}
+
+#[test]
+fn junctions_bridge_light() {
+    let config = Config {
+        kinds: Some(KindSet::from_one(Kind::Light)),
+        junctions: Some(JunctionStyle::Bridge),
+        ..config()
+    };
+    assert_snapshot("junctions_bridge_light", config, SIZE, TICKS);
+}
+
+#[test]
+fn branching() {
+    let config = Config {
+        num_pipes: Some(1),
+        branch_chance: Some(0.1),
+        max_pipes: Some(4),
+        ..config()
+    };
+    assert_snapshot("branching", config, SIZE, TICKS);
+}
+
+#[test]
+fn branching_new_colors() {
+    let config = Config {
+        color_mode: Some(ColorMode::Ansi),
+        num_pipes: Some(1),
+        branch_chance: Some(0.1),
+        inherit_branch_color: Some(false),
+        ..config()
+    };
+    assert_snapshot("branching_new_colors", config, SIZE, 30);
+}
+
+#[test]
+fn reset_threshold() {
+    let config = Config {
+        reset_threshold: Some(0.2),
+        ..config()
+    };
+    assert_snapshot("reset_threshold", config, SIZE, 200);
+}
+
+#[test]
+fn same_seed_same_picture() {
+    let a = pipes_rs::simulate(config(), SIZE, TICKS).unwrap();
+    let b = pipes_rs::simulate(config(), SIZE, TICKS).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn colors_dont_change_geometry() {
+    let characters = |color_mode, palette| {
+        let config = Config {
+            color_mode: Some(color_mode),
+            palette,
+            branch_chance: Some(0.1),
+            ..config()
+        };
+        pipes_rs::simulate(config, SIZE, TICKS).unwrap().to_string()
+    };
+
+    let expected = characters(ColorMode::None, None);
+
+    let color_modes = [
+        ("ansi", ColorMode::Ansi),
+        ("ansi256", ColorMode::Ansi256),
+        ("rgb", ColorMode::Rgb),
+    ];
+    let palettes = [
+        ("default", Palette::Default),
+        ("darker", Palette::Darker),
+        ("pastel", Palette::Pastel),
+        ("matrix", Palette::Matrix),
+    ];
+
+    for (color_mode_name, color_mode) in color_modes {
+        for (palette_name, palette) in palettes {
+            assert_eq!(
+                characters(color_mode, Some(palette)),
+                expected,
+                "{color_mode_name} with the {palette_name} palette"
+            );
+        }
+    }
+}
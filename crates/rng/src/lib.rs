@@ -1,39 +1,47 @@
-use once_cell::sync::Lazy;
-use parking_lot::Mutex;
 use std::ops::Range;
 
-static RNG: Lazy<Mutex<Rng>> = Lazy::new(|| {
-    let seed = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .expect("system time cannot be before unix epoch")
-        .as_millis() as u64;
-
-    Mutex::new(Rng {
-        rand_32: oorandom::Rand32::new(seed),
-    })
-});
-
-struct Rng {
+/// A seedable source of randomness.
+///
+/// Each `Rng` is its own independent stream,
+/// so separate simulations can each own one without sharing state.
+pub struct Rng {
     rand_32: oorandom::Rand32,
 }
 
-pub fn gen_range(range: Range<u32>) -> u32 {
-    RNG.lock().rand_32.rand_range(range)
-}
-
-pub fn gen_range_float(range: Range<f32>) -> f32 {
-    RNG.lock().rand_32.rand_float() * (range.end - range.start) + range.start
+impl Rng {
+    /// Creates an `Rng` on one of many distinct streams,
+    /// which produce unrelated sequences even when given the same seed.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        Self {
+            rand_32: oorandom::Rand32::new_inc(seed, stream),
+        }
+    }
+
+    pub fn gen_range(&mut self, range: Range<u32>) -> u32 {
+        self.rand_32.rand_range(range)
+    }
+
+    pub fn gen_range_float(&mut self, range: Range<f32>) -> f32 {
+        self.rand_32.rand_float() * (range.end - range.start) + range.start
+    }
+
+    pub fn gen_range_16(&mut self, range: Range<u16>) -> u16 {
+        self.rand_32
+            .rand_range(range.start as u32..range.end as u32) as u16
+    }
+
+    pub fn gen_bool(&mut self, probability: f32) -> bool {
+        assert!(probability >= 0.0);
+        assert!(probability <= 1.0);
+
+        self.rand_32.rand_float() < probability
+    }
 }
 
-pub fn gen_range_16(range: Range<u16>) -> u16 {
-    RNG.lock()
-        .rand_32
-        .rand_range(range.start as u32..range.end as u32) as u16
-}
-
-pub fn gen_bool(probability: f32) -> bool {
-    assert!(probability >= 0.0);
-    assert!(probability <= 1.0);
-
-    RNG.lock().rand_32.rand_float() < probability
+/// Generates a seed from the current time in milliseconds.
+pub fn seed_from_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("system time cannot be before unix epoch")
+        .as_millis() as u64
 }
@@ -0,0 +1,67 @@
+mod grid;
+mod recorder;
+mod stdout;
+#[cfg(unix)]
+mod tty;
+mod writer;
+
+pub use grid::{Cell, Grid, GridBackend};
+pub use recorder::Recorder;
+pub use stdout::CrosstermBackend;
+pub use writer::WriteBackend;
+
+use crossterm::event::Event as CrosstermEvent;
+use std::io::Write;
+use std::time::Duration;
+
+/// Somewhere for a [`Terminal`](crate::Terminal) to draw to.
+///
+/// Everything drawn is written as a stream of escape sequences;
+/// the remaining methods cover what a plain byte stream can’t.
+pub trait Backend: Write {
+    /// The size of the backend in columns and rows.
+    fn size(&self) -> anyhow::Result<(u16, u16)>;
+
+    fn set_raw_mode(&mut self, _enabled: bool) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after the flush that finishes each frame,
+    /// as opposed to flushes that only change modes or show a message.
+    fn end_frame(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Returns the next input event if one is ready, without blocking.
+    fn poll_event(&mut self) -> anyhow::Result<Option<CrosstermEvent>> {
+        Ok(None)
+    }
+
+    /// Sends `request` to the terminal and returns its reply,
+    /// or `None` if it doesn’t reply within `timeout` or there is no terminal to ask.
+    fn query(&mut self, _request: &[u8], _timeout: Duration) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        (**self).size()
+    }
+
+    fn set_raw_mode(&mut self, enabled: bool) -> anyhow::Result<()> {
+        (**self).set_raw_mode(enabled)
+    }
+
+    fn end_frame(&mut self) -> anyhow::Result<()> {
+        (**self).end_frame()
+    }
+
+    fn poll_event(&mut self) -> anyhow::Result<Option<CrosstermEvent>> {
+        (**self).poll_event()
+    }
+
+    fn query(&mut self, request: &[u8], timeout: Duration) -> anyhow::Result<Option<Vec<u8>>> {
+        (**self).query(request, timeout)
+    }
+}
@@ -0,0 +1,303 @@
+use super::Backend;
+use crate::Color;
+use std::fmt;
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthChar;
+
+/// Draws to an in-memory grid of cells,
+/// by interpreting the escape sequences a [`Terminal`](crate::Terminal) emits.
+///
+/// Useful for running headlessly and inspecting what would have been shown.
+pub struct GridBackend {
+    grid: Grid,
+    cursor: (u16, u16),
+    color: Option<Color>,
+    bold: bool,
+    pending: Vec<u8>,
+}
+
+impl GridBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            grid: Grid::new(width, height),
+            cursor: (0, 0),
+            color: None,
+            bold: false,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Interprets as much of the pending input as possible,
+    /// leaving any incomplete sequence at the end for next time.
+    fn process(&mut self) {
+        let mut i = 0;
+
+        while i < self.pending.len() {
+            let consumed = match self.pending[i] {
+                0x1b => self.process_escape(i),
+                b'\r' => {
+                    self.cursor.0 = 0;
+                    Some(1)
+                }
+                b'\n' => {
+                    self.cursor.1 = self.cursor.1.saturating_add(1);
+                    Some(1)
+                }
+                byte if byte < 0x20 => Some(1),
+                byte => self.process_char(i, byte),
+            };
+
+            match consumed {
+                Some(n) => i += n,
+                None => break,
+            }
+        }
+
+        self.pending.drain(..i);
+    }
+
+    fn process_escape(&mut self, start: usize) -> Option<usize> {
+        let bytes = &self.pending[start..];
+
+        match bytes.get(1)? {
+            b'[' => {
+                let end = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b))? + 2;
+                let params = String::from_utf8_lossy(&bytes[2..end]).into_owned();
+                let action = bytes[end];
+                self.process_csi(&params, action);
+                Some(end + 1)
+            }
+
+            // operating system commands are terminated by BEL or ST
+            b']' => {
+                let mut i = 2;
+                loop {
+                    match bytes.get(i)? {
+                        0x07 => return Some(i + 1),
+                        0x1b if *bytes.get(i + 1)? == b'\\' => return Some(i + 2),
+                        _ => i += 1,
+                    }
+                }
+            }
+
+            _ => Some(2),
+        }
+    }
+
+    fn process_csi(&mut self, params: &str, action: u8) {
+        // private modes such as the alternate screen have no effect on the grid
+        if params.starts_with('?') {
+            return;
+        }
+
+        let mut params = params.split(';').map(|p| p.parse::<u16>().ok());
+
+        match action {
+            b'H' | b'f' => {
+                let row = params.next().flatten().unwrap_or(1).max(1);
+                let column = params.next().flatten().unwrap_or(1).max(1);
+                self.cursor = (column - 1, row - 1);
+            }
+
+            b'J' => {
+                if let Some(Some(2 | 3)) = params.next() {
+                    self.grid.clear();
+                }
+            }
+
+            b'm' => {
+                while let Some(param) = params.next() {
+                    match param.unwrap_or(0) {
+                        0 => {
+                            self.color = None;
+                            self.bold = false;
+                        }
+                        1 => self.bold = true,
+                        22 => self.bold = false,
+                        38 => match params.next().flatten() {
+                            Some(5) => {
+                                let value = params.next().flatten().unwrap_or(0);
+                                self.color = Some(ansi_value_to_color(value as u8));
+                            }
+                            Some(2) => {
+                                let mut next = || params.next().flatten().unwrap_or(0) as u8;
+                                self.color = Some(Color::Rgb {
+                                    r: next(),
+                                    g: next(),
+                                    b: next(),
+                                });
+                            }
+                            _ => {}
+                        },
+                        39 => self.color = None,
+                        n @ 30..=37 => self.color = Some(ansi_value_to_color((n - 30) as u8)),
+                        n @ 90..=97 => self.color = Some(ansi_value_to_color((n - 90 + 8) as u8)),
+                        _ => {}
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    fn process_char(&mut self, start: usize, first_byte: u8) -> Option<usize> {
+        let len = match first_byte {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Some(1),
+        };
+
+        let bytes = self.pending.get(start..start + len)?;
+        let Some(c) = std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+        else {
+            return Some(len);
+        };
+
+        let (x, y) = self.cursor;
+        self.grid.set(
+            x,
+            y,
+            Some(Cell {
+                c,
+                color: self.color,
+                bold: self.bold,
+            }),
+        );
+
+        let width = c.width().unwrap_or(0) as u16;
+        for offset in 1..width {
+            self.grid.set(x + offset, y, None);
+        }
+        self.cursor.0 = x.saturating_add(width);
+
+        Some(len)
+    }
+}
+
+impl Write for GridBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.process();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for GridBackend {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        Ok((self.grid.width, self.grid.height))
+    }
+}
+
+/// The contents of a terminal, in columns and rows.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid {
+    width: u16,
+    height: u16,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns the cell at the given position,
+    /// or `None` if it is empty or out of bounds.
+    pub fn get(&self, x: u16, y: u16) -> Option<Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.cells[y as usize * self.width as usize + x as usize]
+    }
+
+    pub(crate) fn set(&mut self, x: u16, y: u16, cell: Option<Cell>) {
+        // like a terminal with wrapping disabled, anything out of bounds is dropped
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        self.cells[y as usize * self.width as usize + x as usize] = cell;
+    }
+
+    fn clear(&mut self) {
+        self.cells.fill(None);
+    }
+}
+
+/// Prints the characters of the grid, one line per row.
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            let mut line = String::new();
+            let mut x = 0;
+
+            while x < self.width {
+                match self.get(x, y) {
+                    Some(cell) => {
+                        line.push(cell.c);
+                        x += cell.c.width().unwrap_or(1).max(1) as u16;
+                    }
+                    None => {
+                        line.push(' ');
+                        x += 1;
+                    }
+                }
+            }
+
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cell {
+    pub c: char,
+    pub color: Option<Color>,
+    pub bold: bool,
+}
+
+fn ansi_value_to_color(value: u8) -> Color {
+    match value {
+        1 => Color::DarkRed,
+        2 => Color::DarkGreen,
+        3 => Color::DarkYellow,
+        4 => Color::DarkBlue,
+        5 => Color::DarkMagenta,
+        6 => Color::DarkCyan,
+        9 => Color::Red,
+        10 => Color::Green,
+        11 => Color::Yellow,
+        12 => Color::Blue,
+        13 => Color::Magenta,
+        14 => Color::Cyan,
+        n => Color::AnsiValue(n),
+    }
+}
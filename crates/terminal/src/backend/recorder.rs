@@ -0,0 +1,197 @@
+use super::Backend;
+use crossterm::event::Event as CrosstermEvent;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Resets colors and shows the cursor.
+const RESTORE: &[u8] = b"\x1b[0m\x1b[?25h";
+
+/// Wraps another backend, additionally recording everything drawn
+/// to an [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file.
+///
+/// Each flush becomes one output event.
+/// Timestamps advance by `frame_length` with every frame,
+/// so playback matches the intended frame rate regardless of how long drawing took.
+///
+/// When dropped, a final event resets the colors and shows the cursor again,
+/// in case whatever drew to it never restored them.
+pub struct Recorder<B, W: Write> {
+    inner: B,
+    output: W,
+    frame: Vec<u8>,
+    frame_length: Duration,
+    elapsed: Duration,
+}
+
+impl<B: Backend, W: Write> Recorder<B, W> {
+    pub fn new(inner: B, mut output: W, frame_length: Duration) -> anyhow::Result<Self> {
+        let (width, height) = inner.size()?;
+        writeln!(
+            output,
+            r#"{{"version": 2, "width": {width}, "height": {height}}}"#
+        )?;
+
+        Ok(Self {
+            inner,
+            output,
+            frame: Vec::new(),
+            frame_length,
+            elapsed: Duration::ZERO,
+        })
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+impl<B, W: Write> Recorder<B, W> {
+    fn write_event(&mut self) -> io::Result<()> {
+        if self.frame.is_empty() {
+            return Ok(());
+        }
+
+        let data = String::from_utf8_lossy(&self.frame);
+        write!(self.output, "[{:.6}, \"o\", \"", self.elapsed.as_secs_f64())?;
+        write_json_escaped(&mut self.output, &data)?;
+        writeln!(self.output, "\"]")?;
+        self.output.flush()?;
+
+        self.frame.clear();
+
+        Ok(())
+    }
+}
+
+impl<B: Backend, W: Write> Write for Recorder<B, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.frame.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.write_event()
+    }
+}
+
+impl<B: Backend, W: Write> Backend for Recorder<B, W> {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        self.inner.size()
+    }
+
+    fn set_raw_mode(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.inner.set_raw_mode(enabled)
+    }
+
+    fn end_frame(&mut self) -> anyhow::Result<()> {
+        self.inner.end_frame()?;
+        self.elapsed += self.frame_length;
+        Ok(())
+    }
+
+    fn poll_event(&mut self) -> anyhow::Result<Option<CrosstermEvent>> {
+        self.inner.poll_event()
+    }
+
+    fn query(&mut self, request: &[u8], timeout: Duration) -> anyhow::Result<Option<Vec<u8>>> {
+        self.inner.query(request, timeout)
+    }
+}
+
+impl<B, W: Write> Drop for Recorder<B, W> {
+    fn drop(&mut self) {
+        self.frame.extend_from_slice(RESTORE);
+        let _ = self.write_event();
+    }
+}
+
+fn write_json_escaped(w: &mut impl Write, s: &str) -> io::Result<()> {
+    for c in s.chars() {
+        match c {
+            '"' => w.write_all(b"\\\"")?,
+            '\\' => w.write_all(b"\\\\")?,
+            '\n' => w.write_all(b"\\n")?,
+            '\r' => w.write_all(b"\\r")?,
+            '\t' => w.write_all(b"\\t")?,
+            c if c.is_control() => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{c}")?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WriteBackend;
+    use serde_json::Value;
+
+    /// Records the given writes, flushing after each and ending a frame after all but the first,
+    /// and returns the header and events that were written.
+    fn record(writes: &[&str]) -> (Value, Vec<Value>) {
+        let mut output = Vec::new();
+        {
+            let backend = WriteBackend::new(io::sink(), (10, 4));
+            let mut recorder =
+                Recorder::new(backend, &mut output, Duration::from_millis(20)).unwrap();
+
+            for (i, write) in writes.iter().enumerate() {
+                recorder.write_all(write.as_bytes()).unwrap();
+                recorder.flush().unwrap();
+                if i > 0 {
+                    recorder.end_frame().unwrap();
+                }
+            }
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap());
+        let header = lines.next().unwrap();
+        (header, lines.collect())
+    }
+
+    fn timestamps(events: &[Value]) -> Vec<f64> {
+        events.iter().map(|e| e[0].as_f64().unwrap()).collect()
+    }
+
+    #[test]
+    fn header() {
+        let (header, _) = record(&[]);
+        assert_eq!(
+            header,
+            serde_json::json!({"version": 2, "width": 10, "height": 4})
+        );
+    }
+
+    #[test]
+    fn escapes_control_characters_and_quotes() {
+        let data = "\x1b[1;1H\"a\\b\"\r\n\t\x07┃";
+        let (_, events) = record(&[data]);
+        assert_eq!(events[0][1], "o");
+        assert_eq!(events[0][2], data);
+    }
+
+    #[test]
+    fn timestamps_advance_with_frames() {
+        let (_, events) = record(&["\x1b[?25l", "a", "b", "c"]);
+        assert_eq!(timestamps(&events), [0.0, 0.0, 0.02, 0.04, 0.06]);
+        assert!(timestamps(&events).windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn empty_flushes_are_skipped() {
+        let (_, events) = record(&["a", "", ""]);
+        assert_eq!(timestamps(&events), [0.0, 0.04]);
+    }
+
+    #[test]
+    fn restores_cursor_when_dropped() {
+        let (_, events) = record(&["\x1b[?25l"]);
+        assert_eq!(events.last().unwrap()[2], "\x1b[0m\x1b[?25h");
+    }
+}
@@ -0,0 +1,105 @@
+use super::Backend;
+use crossterm::event::{self, Event as CrosstermEvent};
+use crossterm::{cursor, execute, style, terminal};
+use std::io::{self, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+
+/// Whether the real terminal is in raw mode,
+/// in which case a panic needs to put it back before reporting anything.
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Draws to the real terminal attached to the process’ stdout.
+pub struct CrosstermBackend {
+    stdout: io::StdoutLock<'static>,
+}
+
+impl CrosstermBackend {
+    pub fn new() -> Self {
+        Self {
+            stdout: io::stdout().lock(),
+        }
+    }
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for CrosstermBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        Ok(terminal::size()?)
+    }
+
+    fn set_raw_mode(&mut self, enabled: bool) -> anyhow::Result<()> {
+        if enabled {
+            install_panic_hook();
+            crate::signals::catch()?;
+            terminal::enable_raw_mode()?;
+        } else {
+            terminal::disable_raw_mode()?;
+        }
+        RAW_MODE.store(enabled, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn poll_event(&mut self) -> anyhow::Result<Option<CrosstermEvent>> {
+        if !event::poll(Duration::ZERO)? {
+            return Ok(None);
+        }
+
+        Ok(Some(event::read()?))
+    }
+
+    #[cfg(unix)]
+    fn query(&mut self, request: &[u8], timeout: Duration) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(super::tty::query(&mut self.stdout, request, timeout)?)
+    }
+}
+
+/// Makes panics restore the terminal before printing their message,
+/// since with `panic = "abort"` nothing else gets the chance to.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if RAW_MODE.swap(false, Ordering::Relaxed) {
+                let _ = restore();
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Undoes everything pipes-rs might have done to the terminal,
+/// without knowing what that was.
+fn restore() -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        event::DisableMouseCapture,
+        event::DisableBracketedPaste,
+        event::DisableFocusChange,
+        terminal::EndSynchronizedUpdate,
+        style::ResetColor,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    )
+}
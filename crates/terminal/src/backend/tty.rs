@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant};
+
+/// Asks for the terminal’s primary device attributes,
+/// which every terminal answers.
+///
+/// Sending this after a query means we know the terminal is done replying
+/// without having to wait out the timeout when it doesn’t recognize the query.
+const DEVICE_ATTRIBUTES_REQUEST: &[u8] = b"\x1b[c";
+
+/// Writes `request` to `out` and reads the terminal’s reply from `/dev/tty`,
+/// returning `None` if there is no terminal to read from or it doesn’t reply in time.
+///
+/// Raw mode must be enabled, or the reply would be echoed and held back until a newline.
+pub(super) fn query(
+    out: &mut impl Write,
+    request: &[u8],
+    timeout: Duration,
+) -> io::Result<Option<Vec<u8>>> {
+    let Ok(mut tty) = File::open("/dev/tty") else {
+        return Ok(None);
+    };
+
+    out.write_all(request)?;
+    out.write_all(DEVICE_ATTRIBUTES_REQUEST)?;
+    out.flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut reply = Vec::new();
+
+    loop {
+        if let Some(start) = find_device_attributes(&reply) {
+            reply.truncate(start);
+            return Ok(Some(reply));
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !wait_readable(&tty, remaining)? {
+            return Ok(None);
+        }
+
+        let mut buf = [0; 256];
+        match tty.read(&mut buf)? {
+            0 => return Ok(None),
+            n => reply.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+/// Waits up to `timeout` for `file` to have something to read.
+fn wait_readable(file: &File, timeout: Duration) -> io::Result<bool> {
+    let mut fd = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().try_into().unwrap_or(libc::c_int::MAX);
+
+    loop {
+        // SAFETY: `fd` is a single valid pollfd that outlives the call.
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            -1 => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+            0 => return Ok(false),
+            _ => return Ok(true),
+        }
+    }
+}
+
+/// Finds where the reply to [`DEVICE_ATTRIBUTES_REQUEST`] starts,
+/// which looks like `CSI ? 62 ; 22 c`.
+fn find_device_attributes(reply: &[u8]) -> Option<usize> {
+    (0..reply.len()).find(|&start| {
+        let Some(params) = reply[start..].strip_prefix(b"\x1b[?") else {
+            return false;
+        };
+        let end = params
+            .iter()
+            .position(|&b| !(b.is_ascii_digit() || b == b';'));
+
+        end.is_some_and(|end| params[end] == b'c')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_device_attributes() {
+        assert_eq!(find_device_attributes(b"\x1b[?62;22c"), Some(0));
+    }
+
+    #[test]
+    fn reply_before_device_attributes() {
+        let reply = b"\x1b[?2026;2$y\x1b[?62;22c";
+        assert_eq!(find_device_attributes(reply), Some(11));
+    }
+
+    #[test]
+    fn reply_split_before_device_attributes() {
+        let mut reply = b"\x1b[?2026;2$y\x1b[?62;".to_vec();
+        assert_eq!(find_device_attributes(&reply), None);
+
+        reply.extend_from_slice(b"22c");
+        assert_eq!(find_device_attributes(&reply), Some(11));
+    }
+
+    #[test]
+    fn trailing_garbage_after_device_attributes() {
+        let reply = b"\x1b[?2026;1$y\x1b[?1;2cgarbage";
+        assert_eq!(find_device_attributes(reply), Some(11));
+    }
+
+    #[test]
+    fn query_reply_is_not_device_attributes() {
+        assert_eq!(find_device_attributes(b"\x1b[?2026;1$y"), None);
+    }
+}
@@ -0,0 +1,34 @@
+use super::Backend;
+use std::io::{self, Write};
+
+/// Draws to any [`Write`] implementor, pretending it is a terminal of a fixed size.
+pub struct WriteBackend<W> {
+    writer: W,
+    size: (u16, u16),
+}
+
+impl<W: Write> WriteBackend<W> {
+    pub fn new(writer: W, size: (u16, u16)) -> Self {
+        Self { writer, size }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for WriteBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> Backend for WriteBackend<W> {
+    fn size(&self) -> anyhow::Result<(u16, u16)> {
+        Ok(self.size)
+    }
+}
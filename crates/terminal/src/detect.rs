@@ -0,0 +1,177 @@
+//! Guesses at what the terminal supports when it can’t be asked directly.
+
+use std::env;
+use std::ffi::OsString;
+
+/// Whether the terminal is one known to support synchronized output (DEC mode 2026).
+pub(crate) fn synchronized_output_from_env() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WEZTERM_EXECUTABLE").is_some()
+        || ["xterm-kitty", "foot", "contour", "alacritty"]
+            .iter()
+            .any(|t| term.starts_with(t))
+        || ["WezTerm", "iTerm.app", "contour"].contains(&term_program.as_str())
+}
+
+/// How many colors the terminal can display.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorSupport {
+    None,
+    Ansi,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Works out how many colors the terminal supports from the environment,
+    /// honoring the `NO_COLOR` convention.
+    pub fn detect() -> Self {
+        Self::detect_from(|name| env::var_os(name))
+    }
+
+    /// Like [`ColorSupport::detect`], but looking up environment variables with `var`.
+    fn detect_from(var: impl Fn(&str) -> Option<OsString>) -> Self {
+        if var("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Self::None;
+        }
+
+        let string = |name| {
+            var(name)
+                .and_then(|v| v.into_string().ok())
+                .unwrap_or_default()
+        };
+        let term = string("TERM");
+        let colorterm = string("COLORTERM");
+        let term_program = string("TERM_PROGRAM");
+
+        if term == "dumb" {
+            return Self::None;
+        }
+
+        // tmux passes on COLORTERM when the outer terminal supports truecolor,
+        // but screen doesn’t translate truecolor escapes,
+        // so an inherited COLORTERM can’t be trusted inside it.
+        let in_screen =
+            var("TMUX").is_none() && (var("STY").is_some() || term.starts_with("screen"));
+        let max = if in_screen {
+            Self::Ansi256
+        } else {
+            Self::TrueColor
+        };
+
+        let support = if colorterm == "truecolor"
+            || colorterm == "24bit"
+            || term.ends_with("-direct")
+            || ["xterm-kitty", "alacritty", "foot", "wezterm", "contour"]
+                .iter()
+                .any(|t| term.starts_with(t))
+            || ["iTerm.app", "WezTerm", "vscode"].contains(&term_program.as_str())
+            || var("WT_SESSION").is_some()
+        {
+            Self::TrueColor
+        } else if term.contains("256color") || in_screen {
+            Self::Ansi256
+        } else {
+            Self::Ansi
+        };
+
+        support.min(max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(vars: &[(&str, &str)]) -> ColorSupport {
+        ColorSupport::detect_from(|name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| OsString::from(v))
+        })
+    }
+
+    #[test]
+    fn no_color() {
+        let vars = [
+            ("NO_COLOR", "1"),
+            ("TERM", "xterm-256color"),
+            ("COLORTERM", "truecolor"),
+        ];
+        assert_eq!(detect(&vars), ColorSupport::None);
+    }
+
+    #[test]
+    fn empty_no_color_is_ignored() {
+        let vars = [("NO_COLOR", ""), ("TERM", "xterm-256color")];
+        assert_eq!(detect(&vars), ColorSupport::Ansi256);
+    }
+
+    #[test]
+    fn dumb() {
+        let vars = [("TERM", "dumb"), ("COLORTERM", "truecolor")];
+        assert_eq!(detect(&vars), ColorSupport::None);
+    }
+
+    #[test]
+    fn colorterm() {
+        for colorterm in ["truecolor", "24bit"] {
+            let vars = [("TERM", "xterm"), ("COLORTERM", colorterm)];
+            assert_eq!(detect(&vars), ColorSupport::TrueColor, "{colorterm}");
+        }
+    }
+
+    #[test]
+    fn direct() {
+        assert_eq!(detect(&[("TERM", "xterm-direct")]), ColorSupport::TrueColor);
+    }
+
+    #[test]
+    fn known_terminal() {
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), ColorSupport::TrueColor);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "iTerm.app")]),
+            ColorSupport::TrueColor
+        );
+    }
+
+    #[test]
+    fn ansi_256() {
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorSupport::Ansi256);
+    }
+
+    #[test]
+    fn ansi() {
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorSupport::Ansi);
+        assert_eq!(detect(&[]), ColorSupport::Ansi);
+    }
+
+    #[test]
+    fn screen_caps_truecolor() {
+        let vars = [("TERM", "screen"), ("COLORTERM", "truecolor")];
+        assert_eq!(detect(&vars), ColorSupport::Ansi256);
+
+        let vars = [
+            ("TERM", "xterm"),
+            ("STY", "1234.pts-0"),
+            ("COLORTERM", "truecolor"),
+        ];
+        assert_eq!(detect(&vars), ColorSupport::Ansi256);
+    }
+
+    #[test]
+    fn tmux_keeps_truecolor() {
+        let vars = [
+            ("TERM", "screen-256color"),
+            ("TMUX", "/tmp/tmux"),
+            ("COLORTERM", "truecolor"),
+        ];
+        assert_eq!(detect(&vars), ColorSupport::TrueColor);
+
+        let vars = [("TERM", "screen"), ("TMUX", "/tmp/tmux")];
+        assert_eq!(detect(&vars), ColorSupport::Ansi);
+    }
+}
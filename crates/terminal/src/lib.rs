@@ -1,32 +1,65 @@
+mod backend;
+mod detect;
+mod renderer;
 mod screen;
+mod signals;
+
+pub use backend::{Backend, Cell, CrosstermBackend, Grid, GridBackend, Recorder, WriteBackend};
+pub use detect::ColorSupport;
+pub use signals::resend_received_signal;
 
 use crossterm::event::{
     self, Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
 };
 use crossterm::{cursor, queue, style, terminal};
+use renderer::Renderer;
 use screen::Screen;
-use std::io::{self, Write};
 use std::num::NonZeroUsize;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthChar;
 
-pub struct Terminal {
+pub struct Terminal<B: Backend = CrosstermBackend> {
     screen: Screen,
-    stdout: io::StdoutLock<'static>,
+    renderer: Renderer,
+    frame: Vec<u8>,
+    synchronized_output: bool,
+    raw_mode: bool,
+    alternate_screen: bool,
+    cursor_hidden: bool,
+    backend: B,
     max_char_width: u16,
     size: (u16, u16),
+    color: Option<Color>,
+    bold: bool,
+    screensaver_since: Option<Instant>,
+    pending_resize: Option<(u16, u16, Instant)>,
 }
 
-impl Terminal {
+/// How long to ignore input after entering screensaver mode,
+/// since some terminals report focus or mouse state as soon as reporting is enabled.
+const SCREENSAVER_GRACE_PERIOD: Duration = Duration::from_millis(500);
+
+/// How long the size has to stay the same before a resize takes effect,
+/// so dragging a window’s edge doesn’t redraw the whole screen for every step.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// How long to wait for the terminal to reply to a query,
+/// which is only reached when it doesn’t answer device attribute requests either.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+const BEGIN_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026h";
+const END_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026l";
+
+impl<B: Backend> Terminal<B> {
     pub fn new(
-        stdout: io::StdoutLock<'static>,
+        backend: B,
         chars: impl Iterator<Item = char>,
         custom_width: Option<NonZeroUsize>,
     ) -> anyhow::Result<Self> {
         let max_char_width = Self::determine_max_char_width(chars, custom_width);
 
         let size = {
-            let (width, height) = terminal::size()?;
+            let (width, height) = backend.size()?;
             (width / max_char_width, height)
         };
 
@@ -34,12 +67,30 @@ impl Terminal {
 
         Ok(Self {
             screen,
-            stdout,
+            renderer: Renderer::default(),
+            frame: Vec::new(),
+            synchronized_output: false,
+            raw_mode: false,
+            alternate_screen: false,
+            cursor_hidden: false,
+            backend,
             max_char_width,
             size,
+            color: None,
+            bold: false,
+            screensaver_since: None,
+            pending_resize: None,
         })
     }
 
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
     fn determine_max_char_width(
         chars: impl Iterator<Item = char>,
         custom_width: Option<NonZeroUsize>,
@@ -53,66 +104,131 @@ impl Terminal {
     }
 
     pub fn enable_bold(&mut self) -> anyhow::Result<()> {
-        queue!(self.stdout, style::SetAttribute(style::Attribute::Bold))?;
+        self.bold = true;
+
         Ok(())
     }
 
     pub fn reset_style(&mut self) -> anyhow::Result<()> {
-        queue!(self.stdout, style::SetAttribute(style::Attribute::Reset))?;
+        self.color = None;
+        self.bold = false;
+
         Ok(())
     }
 
     pub fn set_cursor_visibility(&mut self, visible: bool) -> anyhow::Result<()> {
         if visible {
-            queue!(self.stdout, cursor::Show)?;
+            queue!(self.frame, cursor::Show)?;
         } else {
-            queue!(self.stdout, cursor::Hide)?;
+            queue!(self.frame, cursor::Hide)?;
         }
+        self.cursor_hidden = !visible;
 
         Ok(())
     }
 
     pub fn clear(&mut self) -> anyhow::Result<()> {
-        queue!(self.stdout, terminal::Clear(terminal::ClearType::All))?;
         self.screen.clear();
 
         Ok(())
     }
 
-    pub fn set_raw_mode(&self, enabled: bool) -> anyhow::Result<()> {
-        if enabled {
-            terminal::enable_raw_mode()?;
-        } else {
-            terminal::disable_raw_mode()?;
-        }
+    pub fn set_raw_mode(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.backend.set_raw_mode(enabled)?;
+        self.raw_mode = enabled;
 
         Ok(())
     }
 
     pub fn enter_alternate_screen(&mut self) -> anyhow::Result<()> {
-        queue!(self.stdout, terminal::EnterAlternateScreen)?;
+        queue!(self.frame, terminal::EnterAlternateScreen)?;
+        self.renderer.forget();
+        self.alternate_screen = true;
+
         Ok(())
     }
 
     pub fn leave_alternate_screen(&mut self) -> anyhow::Result<()> {
-        queue!(self.stdout, terminal::LeaveAlternateScreen)?;
+        self.render()?;
+        queue!(self.frame, terminal::LeaveAlternateScreen)?;
+        self.renderer.forget();
+        self.alternate_screen = false;
+
         Ok(())
     }
 
+    /// Makes any key press, mouse movement, paste or focus change exit,
+    /// enabling reporting of those that terminals don’t report by default.
+    pub fn enable_screensaver_mode(&mut self) -> anyhow::Result<()> {
+        queue!(
+            self.frame,
+            event::EnableMouseCapture,
+            event::EnableBracketedPaste,
+            event::EnableFocusChange
+        )?;
+        self.screensaver_since = Some(Instant::now());
+
+        Ok(())
+    }
+
+    pub fn disable_screensaver_mode(&mut self) -> anyhow::Result<()> {
+        queue!(
+            self.frame,
+            event::DisableMouseCapture,
+            event::DisableBracketedPaste,
+            event::DisableFocusChange
+        )?;
+        self.screensaver_since = None;
+
+        Ok(())
+    }
+
+    /// Asks the terminal whether it supports synchronized output (DEC mode 2026),
+    /// guessing from the environment if it can’t be asked.
+    ///
+    /// Raw mode should be enabled first, so the reply isn’t echoed.
+    pub fn supports_synchronized_output(&mut self) -> anyhow::Result<bool> {
+        self.flush_changes()?;
+
+        match self.backend.query(b"\x1b[?2026$p", QUERY_TIMEOUT)? {
+            Some(reply) => Ok(reports_synchronized_output(&reply)),
+            None => Ok(detect::synchronized_output_from_env()),
+        }
+    }
+
+    /// Asks the terminal for its background color with OSC 11,
+    /// returning `None` if it doesn’t say.
+    ///
+    /// Raw mode should be enabled first, so the reply isn’t echoed.
+    pub fn background_color(&mut self) -> anyhow::Result<Option<Color>> {
+        self.flush_changes()?;
+
+        let reply = self.backend.query(b"\x1b]11;?\x1b\\", QUERY_TIMEOUT)?;
+        Ok(reply.as_deref().and_then(parse_background_color))
+    }
+
+    /// Makes each flush display as a whole, so the terminal never shows a half-drawn frame.
+    pub fn set_synchronized_output(&mut self, enabled: bool) {
+        self.synchronized_output = enabled;
+    }
+
     pub fn set_text_color(&mut self, color: Color) -> anyhow::Result<()> {
-        let color = style::Color::from(color);
-        queue!(self.stdout, style::SetForegroundColor(color))?;
+        self.color = Some(color);
 
         Ok(())
     }
 
     pub fn move_cursor_to(&mut self, x: u16, y: u16) -> anyhow::Result<()> {
-        queue!(self.stdout, cursor::MoveTo(x * self.max_char_width, y))?;
         self.screen.move_cursor_to(x as usize, y as usize);
 
         Ok(())
     }
 
+    /// Whether anything has been printed at the given position since the screen was last cleared.
+    pub fn is_covered(&self, x: u16, y: u16) -> bool {
+        self.screen.is_covered(x as usize, y as usize)
+    }
+
     pub fn portion_covered(&self) -> f32 {
         self.screen.portion_covered()
     }
@@ -121,27 +237,147 @@ impl Terminal {
         self.size
     }
 
+    /// Whether there is no room for even a single glyph.
+    pub fn is_too_small(&self) -> bool {
+        self.size.0 == 0 || self.size.1 == 0
+    }
+
+    /// Clears the screen and shows `message` in the top left corner,
+    /// cut short if it doesn’t fit.
+    pub fn show_message(&mut self, message: &str) -> anyhow::Result<()> {
+        let (width, height) = self.backend.size()?;
+
+        self.screen.clear();
+        self.render()?;
+        queue!(self.frame, style::ResetColor)?;
+
+        if height > 0 {
+            let mut columns = 0;
+            let fitting: String = message
+                .chars()
+                .take_while(|c| {
+                    columns += c.width().unwrap_or(0);
+                    columns <= width as usize
+                })
+                .collect();
+            queue!(self.frame, cursor::MoveTo(0, 0), style::Print(fitting))?;
+        }
+
+        self.renderer.forget();
+        self.flush_changes()
+    }
+
+    /// Returns what has been printed since the screen was last cleared,
+    /// laid out in terminal columns.
+    pub fn grid(&self) -> Grid {
+        let mut grid = Grid::new(self.size.0 * self.max_char_width, self.size.1);
+
+        for (x, y, cell) in self.screen.covered() {
+            grid.set(x as u16 * self.max_char_width, y as u16, Some(cell));
+        }
+
+        grid
+    }
+
     pub fn print(&mut self, c: char) -> anyhow::Result<()> {
-        self.screen.print();
-        self.stdout.write_all(c.to_string().as_bytes())?;
+        self.screen.print(Cell {
+            c,
+            color: self.color,
+            bold: self.bold,
+        });
 
         Ok(())
     }
 
+    /// Draws everything that has changed since the last flush,
+    /// handing the backend the whole frame in a single write,
+    /// and tells it the frame is finished.
+    ///
+    /// Until then, printing and clearing only affect the in-memory screen.
     pub fn flush(&mut self) -> anyhow::Result<()> {
-        self.stdout.flush()?;
+        self.flush_changes()?;
+        self.backend.end_frame()
+    }
+
+    /// Like [`flush`](Self::flush), for output that isn’t a frame of its own,
+    /// such as mode changes and messages.
+    fn flush_changes(&mut self) -> anyhow::Result<()> {
+        self.render()?;
+
+        if !self.frame.is_empty() {
+            if self.synchronized_output {
+                self.frame
+                    .splice(0..0, BEGIN_SYNCHRONIZED_UPDATE.iter().copied());
+                self.frame.extend_from_slice(END_SYNCHRONIZED_UPDATE);
+            }
+
+            self.backend.write_all(&self.frame)?;
+            self.frame.clear();
+        }
+        self.backend.flush()?;
+
         Ok(())
     }
 
+    fn render(&mut self) -> anyhow::Result<()> {
+        self.renderer
+            .render(&mut self.screen, self.max_char_width, &mut self.frame)?;
+        Ok(())
+    }
+
+    /// Undoes every change to the terminal’s modes made through this `Terminal`
+    /// and flushes, leaving the terminal as it was found.
+    ///
+    /// This also happens when a `Terminal` in raw mode or on the alternate screen is dropped,
+    /// but calling it directly lets errors be reported.
+    pub fn restore(&mut self) -> anyhow::Result<()> {
+        if self.screensaver_since.is_some() {
+            self.disable_screensaver_mode()?;
+        }
+        self.synchronized_output = false;
+
+        self.render()?;
+        queue!(self.frame, style::ResetColor)?;
+        self.renderer.forget();
+
+        if self.raw_mode {
+            self.set_raw_mode(false)?;
+        }
+        if self.cursor_hidden {
+            self.set_cursor_visibility(true)?;
+        }
+        if self.alternate_screen {
+            self.leave_alternate_screen()?;
+        }
+
+        self.flush_changes()
+    }
+
     pub fn get_event(&mut self) -> anyhow::Result<Option<Event>> {
-        if !event::poll(Duration::ZERO)? {
+        if signals::received().is_some() {
+            return Ok(Some(Event::Exit));
+        }
+
+        if let Some((width, height, at)) = self.pending_resize {
+            if at.elapsed() >= RESIZE_DEBOUNCE {
+                self.pending_resize = None;
+                self.resize(width, height);
+                return Ok(Some(Event::Resize));
+            }
+        }
+
+        let Some(event) = self.backend.poll_event()? else {
             return Ok(None);
+        };
+
+        if let Some(since) = self.screensaver_since {
+            return Ok(self.get_screensaver_event(event, since));
         }
 
-        match event::read()? {
+        match event {
             CrosstermEvent::Resize(width, height) => {
-                self.resize(width, height);
-                Ok(Some(Event::Reset))
+                self.pending_resize = Some((width, height, Instant::now()));
+                Ok(None)
             }
 
             CrosstermEvent::Key(
@@ -158,22 +394,111 @@ impl Terminal {
                 },
             ) => Ok(Some(Event::Exit)),
 
+            #[cfg(unix)]
+            CrosstermEvent::Key(KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            }) => self.suspend(),
+
             CrosstermEvent::Key(KeyEvent {
                 code: KeyCode::Char('r'),
+                kind: KeyEventKind::Press,
                 ..
             }) => Ok(Some(Event::Reset)),
 
+            CrosstermEvent::Key(KeyEvent {
+                code: KeyCode::Char('s'),
+                kind: KeyEventKind::Press,
+                ..
+            }) => Ok(Some(Event::Export)),
+
             _ => Ok(None),
         }
     }
 
+    /// Hands the terminal back to the shell and stops the process,
+    /// as Ctrl-Z would if raw mode didn’t stop it reaching the shell.
+    ///
+    /// Once continued, everything is set up again and the screen is redrawn,
+    /// resizing it if the terminal changed size in the meantime.
+    #[cfg(unix)]
+    fn suspend(&mut self) -> anyhow::Result<Option<Event>> {
+        let raw_mode = self.raw_mode;
+        let alternate_screen = self.alternate_screen;
+        let cursor_hidden = self.cursor_hidden;
+        let screensaver = self.screensaver_since.is_some();
+        let synchronized_output = self.synchronized_output;
+
+        self.restore()?;
+        signal_hook::low_level::raise(signal_hook::consts::SIGTSTP)?;
+
+        // We only get here once continued.
+        if alternate_screen {
+            self.enter_alternate_screen()?;
+        }
+        if raw_mode {
+            self.set_raw_mode(true)?;
+        }
+        if cursor_hidden {
+            self.set_cursor_visibility(false)?;
+        }
+        if screensaver {
+            self.enable_screensaver_mode()?;
+        }
+        self.synchronized_output = synchronized_output;
+
+        let (width, height) = self.backend.size()?;
+        if (width / self.max_char_width, height) != self.size {
+            self.resize(width, height);
+            return Ok(Some(Event::Resize));
+        }
+
+        self.screen.redraw();
+        Ok(None)
+    }
+
+    fn get_screensaver_event(&mut self, event: CrosstermEvent, since: Instant) -> Option<Event> {
+        match event {
+            CrosstermEvent::Resize(width, height) => {
+                self.pending_resize = Some((width, height, Instant::now()));
+                None
+            }
+
+            _ if since.elapsed() < SCREENSAVER_GRACE_PERIOD => None,
+
+            CrosstermEvent::Key(KeyEvent {
+                kind: KeyEventKind::Release,
+                ..
+            }) => None,
+
+            CrosstermEvent::Key(_)
+            | CrosstermEvent::Mouse(_)
+            | CrosstermEvent::Paste(_)
+            | CrosstermEvent::FocusGained
+            | CrosstermEvent::FocusLost => Some(Event::Exit),
+        }
+    }
+
     fn resize(&mut self, width: u16, height: u16) {
-        self.size = (width, height);
-        self.screen.resize(width as usize, height as usize);
+        self.size = (width / self.max_char_width, height);
+        self.screen
+            .resize(self.size.0 as usize, self.size.1 as usize);
+        // Terminals may move the cursor while reflowing their contents.
+        self.renderer.forget();
     }
 }
 
-#[derive(Clone, Copy)]
+impl<B: Backend> Drop for Terminal<B> {
+    fn drop(&mut self) {
+        if self.raw_mode || self.alternate_screen {
+            let _ = self.restore();
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Color {
     Red,
     DarkRed,
@@ -187,7 +512,108 @@ pub enum Color {
     DarkMagenta,
     Cyan,
     DarkCyan,
-    Rgb { r: u8, g: u8, b: u8 },
+    /// An entry in the terminal’s 256-color palette.
+    AnsiValue(u8),
+    Rgb {
+        r: u8,
+        g: u8,
+        b: u8,
+    },
+}
+
+impl Color {
+    /// Approximates the color in RGB,
+    /// using xterm’s default palette for the named colors.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Red => (255, 0, 0),
+            Color::DarkRed => (205, 0, 0),
+            Color::Green => (0, 255, 0),
+            Color::DarkGreen => (0, 205, 0),
+            Color::Yellow => (255, 255, 0),
+            Color::DarkYellow => (205, 205, 0),
+            Color::Blue => (92, 92, 255),
+            Color::DarkBlue => (0, 0, 238),
+            Color::Magenta => (255, 0, 255),
+            Color::DarkMagenta => (205, 0, 205),
+            Color::Cyan => (0, 255, 255),
+            Color::DarkCyan => (0, 205, 205),
+            Color::AnsiValue(value) => ansi_value_to_rgb(value),
+            Color::Rgb { r, g, b } => (r, g, b),
+        }
+    }
+}
+
+/// Checks a reply to a DECRQM query for mode 2026,
+/// which looks like `CSI ? 2026 ; Ps $ y`, where Ps is 1 or 2 if the mode can be
+/// set or reset, 3 if it is permanently set, and 0 or 4 if it isn’t supported.
+fn reports_synchronized_output(reply: &[u8]) -> bool {
+    ["1", "2", "3"].iter().any(|ps| {
+        let supported = format!("\x1b[?2026;{ps}$y");
+        reply
+            .windows(supported.len())
+            .any(|w| w == supported.as_bytes())
+    })
+}
+
+/// Parses a reply to an OSC 11 query, which looks like `OSC 11 ; rgb:RRRR/GGGG/BBBB ST`
+/// with between one and four hex digits per component.
+fn parse_background_color(reply: &[u8]) -> Option<Color> {
+    let reply = std::str::from_utf8(reply).ok()?;
+    let start = reply.find("\x1b]11;rgb:")? + "\x1b]11;rgb:".len();
+    let end = reply[start..].find(['\x1b', '\x07'])? + start;
+
+    let mut components = reply[start..end].split('/').map(|hex| {
+        if !(1..=4).contains(&hex.len()) {
+            return None;
+        }
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let max = 16_u32.pow(hex.len() as u32) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    let mut next = || components.next().flatten();
+    let (r, g, b) = (next()?, next()?, next()?);
+
+    Some(Color::Rgb { r, g, b })
+}
+
+/// Looks up an entry in xterm’s default 256-color palette.
+fn ansi_value_to_rgb(value: u8) -> (u8, u8, u8) {
+    const SYSTEM: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match value {
+        0..=15 => SYSTEM[value as usize],
+        16..=231 => {
+            let i = value - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        232..=255 => {
+            let grey = 8 + (value - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
 }
 
 impl From<Color> for style::Color {
@@ -205,12 +631,321 @@ impl From<Color> for style::Color {
             Color::DarkMagenta => Self::DarkMagenta,
             Color::Cyan => Self::Cyan,
             Color::DarkCyan => Self::DarkCyan,
+            Color::AnsiValue(value) => Self::AnsiValue(value),
             Color::Rgb { r, g, b } => Self::Rgb { r, g, b },
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Event {
     Exit,
     Reset,
+    Export,
+    /// The terminal changed size.
+    /// What was drawn is kept where it still fits,
+    /// but anything that was positioned past the new edges needs to move.
+    Resize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEventState, MouseEvent, MouseEventKind};
+    use std::collections::VecDeque;
+    use std::io::{self, Write};
+
+    /// A backend whose input is queued up ahead of time.
+    struct FakeBackend {
+        size: (u16, u16),
+        events: VecDeque<CrosstermEvent>,
+    }
+
+    impl Write for FakeBackend {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Backend for FakeBackend {
+        fn size(&self) -> anyhow::Result<(u16, u16)> {
+            Ok(self.size)
+        }
+
+        fn poll_event(&mut self) -> anyhow::Result<Option<CrosstermEvent>> {
+            Ok(self.events.pop_front())
+        }
+    }
+
+    fn terminal() -> Terminal<FakeBackend> {
+        let backend = FakeBackend {
+            size: (20, 10),
+            events: VecDeque::new(),
+        };
+        Terminal::new(backend, ['━'].into_iter(), None).unwrap()
+    }
+
+    /// Feeds `event` to the terminal and returns what it makes of it.
+    fn handle(terminal: &mut Terminal<FakeBackend>, event: CrosstermEvent) -> Option<Event> {
+        terminal.backend_mut().events.push_back(event);
+        terminal.get_event().unwrap()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers, kind: KeyEventKind) -> CrosstermEvent {
+        CrosstermEvent::Key(KeyEvent {
+            code,
+            modifiers,
+            kind,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    fn press(c: char) -> CrosstermEvent {
+        key(KeyCode::Char(c), KeyModifiers::NONE, KeyEventKind::Press)
+    }
+
+    fn release(c: char) -> CrosstermEvent {
+        key(KeyCode::Char(c), KeyModifiers::NONE, KeyEventKind::Release)
+    }
+
+    fn mouse_moved() -> CrosstermEvent {
+        CrosstermEvent::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn keys() {
+        let mut terminal = terminal();
+        let ctrl_c = key(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+            KeyEventKind::Press,
+        );
+
+        assert_eq!(handle(&mut terminal, press('q')), Some(Event::Exit));
+        assert_eq!(handle(&mut terminal, ctrl_c), Some(Event::Exit));
+        assert_eq!(handle(&mut terminal, press('r')), Some(Event::Reset));
+        assert_eq!(handle(&mut terminal, press('s')), Some(Event::Export));
+        assert_eq!(handle(&mut terminal, press('c')), None);
+        assert_eq!(handle(&mut terminal, press('x')), None);
+    }
+
+    #[test]
+    fn key_releases_are_ignored() {
+        let mut terminal = terminal();
+
+        for c in ['q', 'r', 's'] {
+            assert_eq!(handle(&mut terminal, release(c)), None, "{c}");
+        }
+    }
+
+    #[test]
+    fn other_input_is_ignored() {
+        let mut terminal = terminal();
+
+        assert_eq!(handle(&mut terminal, mouse_moved()), None);
+        assert_eq!(
+            handle(&mut terminal, CrosstermEvent::Paste("q".into())),
+            None
+        );
+        assert_eq!(handle(&mut terminal, CrosstermEvent::FocusLost), None);
+        assert_eq!(handle(&mut terminal, CrosstermEvent::FocusGained), None);
+    }
+
+    #[test]
+    fn screensaver_exits_on_any_input() {
+        let mut terminal = terminal();
+        terminal.enable_screensaver_mode().unwrap();
+        terminal.screensaver_since = Some(Instant::now() - SCREENSAVER_GRACE_PERIOD);
+
+        for event in [
+            press('x'),
+            mouse_moved(),
+            CrosstermEvent::Paste("x".into()),
+            CrosstermEvent::FocusGained,
+            CrosstermEvent::FocusLost,
+        ] {
+            assert_eq!(
+                handle(&mut terminal, event.clone()),
+                Some(Event::Exit),
+                "{event:?}"
+            );
+        }
+
+        assert_eq!(handle(&mut terminal, release('x')), None);
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(30, 5)), None);
+    }
+
+    #[test]
+    fn screensaver_ignores_input_at_first() {
+        let mut terminal = terminal();
+        terminal.enable_screensaver_mode().unwrap();
+
+        assert_eq!(handle(&mut terminal, mouse_moved()), None);
+        assert_eq!(handle(&mut terminal, CrosstermEvent::FocusGained), None);
+        assert_eq!(handle(&mut terminal, press('q')), None);
+    }
+
+    /// Pretends a pending resize happened `by` earlier than it did.
+    fn age(terminal: &mut Terminal<FakeBackend>, by: Duration) {
+        if let Some((_, _, at)) = &mut terminal.pending_resize {
+            *at -= by;
+        }
+    }
+
+    #[test]
+    fn resizes_wait_for_the_size_to_settle() {
+        let mut terminal = terminal();
+
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(30, 5)), None);
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(40, 6)), None);
+        assert_eq!(terminal.get_event().unwrap(), None);
+        assert_eq!(terminal.size(), (20, 10));
+
+        age(&mut terminal, RESIZE_DEBOUNCE);
+        assert_eq!(terminal.get_event().unwrap(), Some(Event::Resize));
+        assert_eq!(terminal.size(), (40, 6));
+        assert_eq!(terminal.get_event().unwrap(), None);
+    }
+
+    #[test]
+    fn each_resize_restarts_the_wait() {
+        let mut terminal = terminal();
+
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(30, 5)), None);
+        age(&mut terminal, RESIZE_DEBOUNCE / 2);
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(40, 6)), None);
+        age(&mut terminal, RESIZE_DEBOUNCE / 2);
+
+        assert_eq!(terminal.get_event().unwrap(), None);
+        assert_eq!(terminal.size(), (20, 10));
+
+        age(&mut terminal, RESIZE_DEBOUNCE / 2);
+        assert_eq!(terminal.get_event().unwrap(), Some(Event::Resize));
+        assert_eq!(terminal.size(), (40, 6));
+    }
+
+    #[test]
+    fn resizes_settle_in_screensaver_mode() {
+        let mut terminal = terminal();
+        terminal.enable_screensaver_mode().unwrap();
+
+        assert_eq!(handle(&mut terminal, CrosstermEvent::Resize(30, 5)), None);
+        age(&mut terminal, RESIZE_DEBOUNCE);
+        assert_eq!(terminal.get_event().unwrap(), Some(Event::Resize));
+        assert_eq!(terminal.size(), (30, 5));
+    }
+
+    #[test]
+    fn synchronized_output_supported() {
+        for ps in ["1", "2", "3"] {
+            let reply = format!("\x1b[?2026;{ps}$y");
+            assert!(reports_synchronized_output(reply.as_bytes()), "Ps = {ps}");
+        }
+    }
+
+    #[test]
+    fn synchronized_output_unsupported() {
+        assert!(!reports_synchronized_output(b"\x1b[?2026;4$y"));
+        assert!(!reports_synchronized_output(b"\x1b[?2026;0$y"));
+    }
+
+    #[test]
+    fn synchronized_output_without_reply() {
+        // Terminals that don’t recognize DECRQM only answer the device attributes query,
+        // which is cut off before the reply gets here.
+        assert!(!reports_synchronized_output(b""));
+    }
+
+    #[test]
+    fn synchronized_output_other_mode() {
+        assert!(!reports_synchronized_output(b"\x1b[?2027;1$y"));
+    }
+
+    #[test]
+    fn synchronized_output_with_trailing_garbage() {
+        assert!(reports_synchronized_output(b"\x1b[?2026;2$yjunk\x1b[0m"));
+    }
+
+    #[test]
+    fn background_color_four_digits() {
+        assert_eq!(
+            parse_background_color(b"\x1b]11;rgb:ffff/0000/8080\x1b\\"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 0,
+                b: 128
+            })
+        );
+    }
+
+    #[test]
+    fn background_color_two_digits() {
+        assert_eq!(
+            parse_background_color(b"\x1b]11;rgb:ff/00/80\x1b\\"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 0,
+                b: 128
+            })
+        );
+    }
+
+    #[test]
+    fn background_color_one_digit() {
+        assert_eq!(
+            parse_background_color(b"\x1b]11;rgb:f/0/8\x1b\\"),
+            Some(Color::Rgb {
+                r: 255,
+                g: 0,
+                b: 136
+            })
+        );
+    }
+
+    #[test]
+    fn background_color_bel_terminator() {
+        assert_eq!(
+            parse_background_color(b"\x1b]11;rgb:1e1e/1e1e/2e2e\x07"),
+            Some(Color::Rgb {
+                r: 30,
+                g: 30,
+                b: 46
+            })
+        );
+    }
+
+    #[test]
+    fn background_color_malformed() {
+        for reply in [
+            &b""[..],
+            b"\x1b]11;rgb:ffff/0000/8080",
+            b"\x1b]11;rgb:ffff/0000\x1b\\",
+            b"\x1b]11;rgb:fffff/0000/8080\x1b\\",
+            b"\x1b]11;rgb:ffff//8080\x1b\\",
+            b"\x1b]11;rgb:gggg/0000/8080\x1b\\",
+            b"\x1b]11;rgba:ffff/0000/8080/ffff\x1b\\",
+            b"\x1b]10;rgb:ffff/0000/8080\x1b\\",
+        ] {
+            assert_eq!(parse_background_color(reply), None, "{reply:?}");
+        }
+    }
+
+    #[test]
+    fn ansi_values() {
+        assert_eq!(ansi_value_to_rgb(1), (205, 0, 0));
+        assert_eq!(ansi_value_to_rgb(16), (0, 0, 0));
+        assert_eq!(ansi_value_to_rgb(196), (255, 0, 0));
+        assert_eq!(ansi_value_to_rgb(231), (255, 255, 255));
+        assert_eq!(ansi_value_to_rgb(232), (8, 8, 8));
+        assert_eq!(ansi_value_to_rgb(255), (238, 238, 238));
+    }
 }
@@ -0,0 +1,171 @@
+use crate::screen::Screen;
+use crate::{Cell, Color};
+use crossterm::{cursor, queue, style, terminal};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthChar;
+
+/// Turns changes to the [`Screen`] into escape sequences,
+/// remembering the terminal’s cursor position and style
+/// so it only sends the ones that actually change something.
+#[derive(Default)]
+pub(crate) struct Renderer {
+    cursor: Option<(u16, u16)>,
+    color: Option<Option<Color>>,
+    bold: Option<bool>,
+}
+
+const BLANK: Cell = Cell {
+    c: ' ',
+    color: None,
+    bold: false,
+};
+
+impl Renderer {
+    /// Forgets the terminal’s state,
+    /// for when something else may have moved the cursor or changed the style.
+    pub(crate) fn forget(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn render(
+        &mut self,
+        screen: &mut Screen,
+        max_char_width: u16,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        if screen.take_clear() {
+            queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        }
+
+        for (x, y, cell) in screen.take_changes() {
+            let cell = cell.unwrap_or(BLANK);
+            let (x, y) = (x as u16 * max_char_width, y as u16);
+
+            if self.cursor != Some((x, y)) {
+                queue!(out, cursor::MoveTo(x, y))?;
+            }
+
+            if self.bold != Some(cell.bold) {
+                let attribute = if cell.bold {
+                    style::Attribute::Bold
+                } else {
+                    style::Attribute::NormalIntensity
+                };
+                queue!(out, style::SetAttribute(attribute))?;
+                self.bold = Some(cell.bold);
+            }
+
+            if self.color != Some(cell.color) {
+                let color = cell.color.map_or(style::Color::Reset, Into::into);
+                queue!(out, style::SetForegroundColor(color))?;
+                self.color = Some(cell.color);
+            }
+
+            out.write_all(cell.c.encode_utf8(&mut [0; 4]).as_bytes())?;
+
+            let width = cell.c.width().unwrap_or(0) as u16;
+            self.cursor = Some((x + width, y));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(color: Option<Color>) -> Cell {
+        Cell {
+            c: '━',
+            color,
+            bold: false,
+        }
+    }
+
+    fn render(renderer: &mut Renderer, screen: &mut Screen) -> Vec<u8> {
+        let mut out = Vec::new();
+        renderer.render(screen, 1, &mut out).unwrap();
+        out
+    }
+
+    /// Counts the control sequences in `out` that end in `final_byte`.
+    fn count_csi(out: &[u8], final_byte: u8) -> usize {
+        out.windows(2)
+            .enumerate()
+            .filter(|(_, w)| w == b"\x1b[")
+            .filter(|(i, _)| {
+                out[i + 2..]
+                    .iter()
+                    .find(|b| !(b.is_ascii_digit() || **b == b';'))
+                    .is_some_and(|&b| b == final_byte)
+            })
+            .count()
+    }
+
+    fn count(out: &[u8], needle: &[u8]) -> usize {
+        out.windows(needle.len()).filter(|w| *w == needle).count()
+    }
+
+    #[test]
+    fn adjacent_cells_move_once() {
+        let mut screen = Screen::new(4, 2);
+        screen.move_cursor_to(1, 1);
+        screen.print(cell(None));
+        screen.move_cursor_to(2, 1);
+        screen.print(cell(None));
+
+        let out = render(&mut Renderer::default(), &mut screen);
+        assert_eq!(count_csi(&out, b'H'), 1);
+        assert_eq!(count(&out, "━".as_bytes()), 2);
+    }
+
+    #[test]
+    fn gap_needs_another_move() {
+        let mut screen = Screen::new(4, 2);
+        screen.move_cursor_to(0, 0);
+        screen.print(cell(None));
+        screen.move_cursor_to(2, 0);
+        screen.print(cell(None));
+
+        let out = render(&mut Renderer::default(), &mut screen);
+        assert_eq!(count_csi(&out, b'H'), 2);
+    }
+
+    #[test]
+    fn same_color_set_once() {
+        let mut screen = Screen::new(4, 2);
+        for x in 0..3 {
+            screen.move_cursor_to(x, 0);
+            screen.print(cell(Some(Color::Red)));
+        }
+
+        let out = render(&mut Renderer::default(), &mut screen);
+        assert_eq!(count(&out, b"\x1b[38;"), 1);
+    }
+
+    #[test]
+    fn color_change_sets_color_again() {
+        let mut screen = Screen::new(4, 2);
+        screen.move_cursor_to(0, 0);
+        screen.print(cell(Some(Color::Red)));
+        screen.move_cursor_to(1, 0);
+        screen.print(cell(Some(Color::Blue)));
+
+        let out = render(&mut Renderer::default(), &mut screen);
+        assert_eq!(count(&out, b"\x1b[38;"), 2);
+    }
+
+    #[test]
+    fn unchanged_cell_emits_nothing() {
+        let mut renderer = Renderer::default();
+        let mut screen = Screen::new(4, 2);
+        screen.move_cursor_to(1, 0);
+        screen.print(cell(Some(Color::Red)));
+        assert!(!render(&mut renderer, &mut screen).is_empty());
+
+        screen.move_cursor_to(1, 0);
+        screen.print(cell(Some(Color::Red)));
+        assert!(render(&mut renderer, &mut screen).is_empty());
+    }
+}
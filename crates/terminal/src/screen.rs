@@ -1,5 +1,14 @@
+use crate::Cell;
+
+/// A double-buffered model of the screen:
+/// `cells` holds what should be on screen,
+/// and `shown` holds what the terminal was last told to display.
 pub(crate) struct Screen {
-    cells: Vec<Cell>,
+    cells: Vec<Option<Cell>>,
+    shown: Vec<Option<Cell>>,
+    dirty: Vec<usize>,
+    is_dirty: Vec<bool>,
+    needs_clear: bool,
     cursor: (usize, usize),
     width: usize,
     height: usize,
@@ -9,7 +18,11 @@ pub(crate) struct Screen {
 impl Screen {
     pub(crate) fn new(width: usize, height: usize) -> Self {
         Self {
-            cells: vec![Cell { is_covered: false }; width * height],
+            cells: vec![None; width * height],
+            shown: vec![None; width * height],
+            dirty: Vec::new(),
+            is_dirty: vec![false; width * height],
+            needs_clear: false,
             cursor: (0, 0),
             width,
             height,
@@ -17,13 +30,25 @@ impl Screen {
         }
     }
 
+    /// Changes the size of the screen, keeping whatever was drawn
+    /// where it still fits and leaving any new space blank.
     pub(crate) fn resize(&mut self, width: usize, height: usize) {
-        self.cells
-            .resize(width * height, Cell { is_covered: false });
+        let mut cells = vec![None; width * height];
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                cells[y * width + x] = self.cells[y * self.width + x];
+            }
+        }
+
+        self.num_covered = cells.iter().filter(|c| c.is_some()).count();
+        self.cells = cells;
+        self.shown = vec![None; width * height];
+        self.is_dirty = vec![false; width * height];
+        self.dirty.clear();
         self.cursor = (0, 0);
         self.width = width;
         self.height = height;
-        self.clear();
+        self.redraw();
     }
 
     pub(crate) fn move_cursor_to(&mut self, x: usize, y: usize) {
@@ -33,35 +58,163 @@ impl Screen {
         self.cursor = (x, y);
     }
 
-    pub(crate) fn print(&mut self) {
-        let current_cell = self.current_cell();
-        if !current_cell.is_covered {
-            current_cell.is_covered = true;
+    pub(crate) fn print(&mut self, cell: Cell) {
+        let index = self.cursor.1 * self.width + self.cursor.0;
+
+        if self.cells[index].replace(cell).is_none() {
             self.num_covered += 1;
         }
+
+        if !self.is_dirty[index] {
+            self.is_dirty[index] = true;
+            self.dirty.push(index);
+        }
     }
 
     pub(crate) fn clear(&mut self) {
-        for cell in &mut self.cells {
-            cell.is_covered = false;
-        }
+        self.cells.fill(None);
+        self.shown.fill(None);
+        self.is_dirty.fill(false);
+        self.dirty.clear();
+        self.needs_clear = true;
         self.num_covered = 0;
     }
 
+    /// Forgets what the terminal is showing,
+    /// so the next render clears it and draws every covered cell again.
+    pub(crate) fn redraw(&mut self) {
+        self.shown.fill(None);
+        self.needs_clear = true;
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            if cell.is_some() && !self.is_dirty[i] {
+                self.is_dirty[i] = true;
+                self.dirty.push(i);
+            }
+        }
+    }
+
+    /// Returns whether the screen has been cleared since this was last called,
+    /// in which case the terminal needs to be cleared before drawing any changes.
+    pub(crate) fn take_clear(&mut self) -> bool {
+        std::mem::take(&mut self.needs_clear)
+    }
+
+    /// Iterates in reading order over every cell whose contents differ from what is shown,
+    /// marking each as shown.
+    pub(crate) fn take_changes(
+        &mut self,
+    ) -> impl Iterator<Item = (usize, usize, Option<Cell>)> + '_ {
+        self.dirty.sort_unstable();
+
+        let width = self.width;
+        let cells = &self.cells;
+        let shown = &mut self.shown;
+        let is_dirty = &mut self.is_dirty;
+
+        self.dirty.drain(..).filter_map(move |i| {
+            is_dirty[i] = false;
+            if shown[i] == cells[i] {
+                return None;
+            }
+
+            shown[i] = cells[i];
+            Some((i % width, i / width, cells[i]))
+        })
+    }
+
+    pub(crate) fn is_covered(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x].is_some()
+    }
+
     pub(crate) fn portion_covered(&self) -> f32 {
         debug_assert_eq!(
             self.num_covered,
-            self.cells.iter().filter(|c| c.is_covered).count()
+            self.cells.iter().filter(|c| c.is_some()).count()
         );
         self.num_covered as f32 / self.cells.len() as f32
     }
 
-    fn current_cell(&mut self) -> &mut Cell {
-        &mut self.cells[self.cursor.1 * self.width + self.cursor.0]
+    /// Iterates over every covered cell along with its position.
+    pub(crate) fn covered(&self) -> impl Iterator<Item = (usize, usize, Cell)> + '_ {
+        self.cells.iter().enumerate().filter_map(|(i, cell)| {
+            let cell = (*cell)?;
+            Some((i % self.width, i / self.width, cell))
+        })
     }
 }
 
-#[derive(Clone, Copy)]
-struct Cell {
-    is_covered: bool,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(c: char) -> Cell {
+        Cell {
+            c,
+            color: None,
+            bold: false,
+        }
+    }
+
+    /// A 3×2 screen with a different character in each corner,
+    /// already rendered so nothing is pending.
+    fn screen() -> Screen {
+        let mut screen = Screen::new(3, 2);
+        for (x, y, c) in [(0, 0, 'a'), (2, 0, 'b'), (0, 1, 'c'), (2, 1, 'd')] {
+            screen.move_cursor_to(x, y);
+            screen.print(cell(c));
+        }
+        screen.take_changes().for_each(drop);
+        screen
+    }
+
+    fn covered(screen: &Screen) -> Vec<(usize, usize, char)> {
+        screen
+            .covered()
+            .map(|(x, y, cell)| (x, y, cell.c))
+            .collect()
+    }
+
+    #[test]
+    fn grow_keeps_cells() {
+        let mut screen = screen();
+        screen.resize(5, 4);
+
+        assert_eq!(
+            covered(&screen),
+            [(0, 0, 'a'), (2, 0, 'b'), (0, 1, 'c'), (2, 1, 'd')]
+        );
+        assert_eq!(screen.portion_covered(), 4.0 / 20.0);
+        assert!(!screen.is_covered(3, 0));
+    }
+
+    #[test]
+    fn shrink_crops_cells() {
+        let mut screen = screen();
+        screen.resize(2, 1);
+
+        assert_eq!(covered(&screen), [(0, 0, 'a')]);
+        assert_eq!(screen.portion_covered(), 0.5);
+    }
+
+    #[test]
+    fn resize_redraws_everything() {
+        let mut screen = screen();
+        screen.resize(4, 2);
+
+        assert!(screen.take_clear());
+        let changes: Vec<_> = screen
+            .take_changes()
+            .map(|(x, y, cell)| (x, y, cell.map(|cell| cell.c)))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (0, 0, Some('a')),
+                (2, 0, Some('b')),
+                (0, 1, Some('c')),
+                (2, 1, Some('d'))
+            ]
+        );
+    }
 }
@@ -0,0 +1,51 @@
+//! Turns signals that would otherwise kill the process
+//! into a request to exit, so the terminal can be restored first.
+
+#[cfg(unix)]
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(unix)]
+use std::sync::{Arc, OnceLock};
+
+/// The last signal received, or 0 if there hasn’t been one.
+#[cfg(unix)]
+static RECEIVED: OnceLock<Arc<AtomicUsize>> = OnceLock::new();
+
+/// Starts catching SIGTERM, SIGHUP and SIGINT, if not already.
+pub(crate) fn catch() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    if RECEIVED.get().is_none() {
+        let received = RECEIVED.get_or_init(|| Arc::new(AtomicUsize::new(0)));
+        for signal in [SIGTERM, SIGHUP, SIGINT] {
+            signal_hook::flag::register_usize(signal, Arc::clone(received), signal as usize)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the signal that was caught, if any.
+pub(crate) fn received() -> Option<i32> {
+    #[cfg(unix)]
+    if let Some(received) = RECEIVED.get() {
+        return match received.load(Ordering::Relaxed) {
+            0 => None,
+            signal => Some(signal as i32),
+        };
+    }
+
+    None
+}
+
+/// Once the terminal has been restored,
+/// lets a caught signal have the effect it would have had,
+/// so whoever sent it sees the process die of it.
+pub fn resend_received_signal() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    if let Some(signal) = received() {
+        signal_hook::low_level::emulate_default_handler(signal)?;
+    }
+
+    Ok(())
+}